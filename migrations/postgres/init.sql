@@ -0,0 +1,49 @@
+-- Add migration script here
+CREATE TABLE CAMPAIGN (
+    NAME TEXT NOT NULL,
+    DESCRIPTION TEXT,
+    WHITELIST BOOLEAN NOT NULL DEFAULT TRUE,
+    REQUIRED_ROLES TEXT NOT NULL DEFAULT '',
+    CHAINS TEXT NOT NULL DEFAULT 'Kusama,Moonbeam,Moonriver',
+    START_DATE TIMESTAMP,
+    END_DATE TIMESTAMP,
+    PRIMARY KEY (NAME)
+);
+
+CREATE TABLE CAMPAIGN_WHITELIST (
+    CAMPAIGN TEXT NOT NULL REFERENCES CAMPAIGN (NAME),
+    ADDRESS TEXT NOT NULL,
+    PRIMARY KEY (CAMPAIGN, ADDRESS)
+);
+
+CREATE TABLE CAMPAIGN_RULE (
+    CAMPAIGN TEXT NOT NULL REFERENCES CAMPAIGN (NAME),
+    KIND TEXT NOT NULL,
+    CONTRACT TEXT,
+    MIN_AMOUNT TEXT NOT NULL
+);
+
+CREATE TABLE SIGNED (
+    USER_ID TEXT NOT NULL,
+    USER_TAG TEXT NOT NULL,
+    CAMPAIGN TEXT NOT NULL REFERENCES CAMPAIGN (NAME),
+    KSM_ADDRESS TEXT NOT NULL,
+    GLMR_ADDRESS TEXT NOT NULL,
+    ROLES TEXT,
+    AVATAR TEXT,
+    CREATE_DATE TIMESTAMP NOT NULL,
+    PRIMARY KEY (USER_ID, CAMPAIGN)
+);
+
+CREATE TABLE users (
+    USER_ID TEXT NOT NULL,
+    USER_TAG TEXT NOT NULL,
+    CAMPAIGN TEXT NOT NULL REFERENCES CAMPAIGN (NAME),
+    ADDRESS_TYPE TEXT NOT NULL,
+    ADDRESS TEXT NOT NULL,
+    ROLES TEXT,
+    AVATAR TEXT,
+    CREATE_DATE TIMESTAMP NOT NULL,
+    UPDATE_DATE TIMESTAMP NOT NULL,
+    PRIMARY KEY (USER_ID, CAMPAIGN, ADDRESS_TYPE)
+);
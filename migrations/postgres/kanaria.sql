@@ -0,0 +1,2 @@
+-- The Kanaria drop is the first campaign, as on SQLite.
+INSERT INTO CAMPAIGN (NAME, DESCRIPTION) VALUES ('kanaria', 'Kanaria drop') ON CONFLICT (NAME) DO NOTHING;
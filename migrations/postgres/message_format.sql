@@ -0,0 +1,2 @@
+-- How the GLMR address was encoded into the signed message
+ALTER TABLE SIGNED ADD COLUMN FORMAT TEXT;
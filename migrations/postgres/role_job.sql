@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TABLE ROLE_JOB (
+    ID BIGSERIAL PRIMARY KEY,
+    USER_ID TEXT NOT NULL,
+    ROLE_ID TEXT NOT NULL,
+    ACTION TEXT NOT NULL,
+    STATUS TEXT NOT NULL DEFAULT 'pending',
+    ATTEMPTS INTEGER NOT NULL DEFAULT 0,
+    LAST_ERROR TEXT,
+    NEXT_ATTEMPT TIMESTAMP NOT NULL,
+    CREATE_DATE TIMESTAMP NOT NULL,
+    UPDATE_DATE TIMESTAMP NOT NULL
+);
+CREATE INDEX ROLE_JOB_DUE ON ROLE_JOB (STATUS, NEXT_ATTEMPT);
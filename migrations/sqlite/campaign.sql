@@ -0,0 +1,59 @@
+-- Add migration script here
+CREATE TABLE CAMPAIGN (
+    NAME TEXT NOT NULL,
+    DESCRIPTION TEXT,
+    WHITELIST BOOLEAN NOT NULL DEFAULT 1,
+    REQUIRED_ROLES TEXT NOT NULL DEFAULT '',
+    CHAINS TEXT NOT NULL DEFAULT 'Kusama,Moonbeam,Moonriver',
+    START_DATE TIMESTAMP,
+    END_DATE TIMESTAMP,
+    PRIMARY KEY (NAME)
+);
+
+CREATE TABLE CAMPAIGN_WHITELIST (
+    CAMPAIGN TEXT NOT NULL,
+    ADDRESS TEXT NOT NULL,
+    PRIMARY KEY (CAMPAIGN, ADDRESS),
+    FOREIGN KEY (CAMPAIGN) REFERENCES CAMPAIGN (NAME)
+);
+
+-- The Kanaria drop becomes the first campaign.
+INSERT INTO CAMPAIGN (NAME, DESCRIPTION) VALUES ('kanaria', 'Kanaria drop');
+INSERT INTO CAMPAIGN_WHITELIST (CAMPAIGN, ADDRESS) SELECT 'kanaria', KSM_ADDRESS FROM KANARIA;
+DROP TABLE KANARIA;
+
+-- Registrations are stored per campaign.
+ALTER TABLE SIGNED RENAME TO SIGNED_OLD;
+CREATE TABLE SIGNED (
+    USER_ID TEXT NOT NULL,
+    USER_TAG TEXT NOT NULL,
+    CAMPAIGN TEXT NOT NULL,
+    KSM_ADDRESS TEXT NOT NULL,
+    GLMR_ADDRESS TEXT NOT NULL,
+    ROLES TEXT,
+    AVATAR TEXT,
+    CREATE_DATE TIMESTAMP NOT NULL,
+    PRIMARY KEY (USER_ID, CAMPAIGN),
+    FOREIGN KEY (CAMPAIGN) REFERENCES CAMPAIGN (NAME)
+);
+INSERT INTO SIGNED (USER_ID, USER_TAG, CAMPAIGN, KSM_ADDRESS, GLMR_ADDRESS, ROLES, AVATAR, CREATE_DATE)
+SELECT USER_ID, USER_TAG, 'kanaria', KSM_ADDRESS, GLMR_ADDRESS, ROLES, AVATAR, CREATE_DATE FROM SIGNED_OLD;
+DROP TABLE SIGNED_OLD;
+
+ALTER TABLE users RENAME TO users_old;
+CREATE TABLE users (
+    USER_ID TEXT NOT NULL,
+    USER_TAG TEXT NOT NULL,
+    CAMPAIGN TEXT NOT NULL,
+    ADDRESS_TYPE TEXT NOT NULL,
+    ADDRESS TEXT NOT NULL,
+    ROLES TEXT,
+    AVATAR TEXT,
+    CREATE_DATE TIMESTAMP NOT NULL,
+    UPDATE_DATE TIMESTAMP NOT NULL,
+    PRIMARY KEY (USER_ID, CAMPAIGN, ADDRESS_TYPE),
+    FOREIGN KEY (CAMPAIGN) REFERENCES CAMPAIGN (NAME)
+);
+INSERT INTO users (USER_ID, USER_TAG, CAMPAIGN, ADDRESS_TYPE, ADDRESS, ROLES, AVATAR, CREATE_DATE, UPDATE_DATE)
+SELECT USER_ID, USER_TAG, 'kanaria', ADDRESS_TYPE, ADDRESS, ROLES, AVATAR, CREATE_DATE, UPDATE_DATE FROM users_old;
+DROP TABLE users_old;
@@ -0,0 +1,8 @@
+-- Add migration script here
+CREATE TABLE CAMPAIGN_RULE (
+    CAMPAIGN TEXT NOT NULL,
+    KIND TEXT NOT NULL,
+    CONTRACT TEXT,
+    MIN_AMOUNT TEXT NOT NULL,
+    FOREIGN KEY (CAMPAIGN) REFERENCES CAMPAIGN (NAME)
+)
@@ -0,0 +1,2 @@
+-- MetaMask signatures of the Kusama address only prove the Moonbeam key, campaigns opt in to them
+ALTER TABLE CAMPAIGN ADD COLUMN EVM_SIGNATURES BOOLEAN NOT NULL DEFAULT 0;
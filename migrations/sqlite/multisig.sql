@@ -0,0 +1,4 @@
+-- Multisig accounts are signed for by one of their signatories
+ALTER TABLE SIGNED ADD COLUMN SIGNER TEXT;
+ALTER TABLE SIGNED ADD COLUMN SIGNATORIES TEXT;
+ALTER TABLE SIGNED ADD COLUMN THRESHOLD INTEGER;
@@ -0,0 +1,4 @@
+-- Keep what each signature was accepted on, so it can be verified again
+ALTER TABLE SIGNED ADD COLUMN SIGNATURE TEXT;
+ALTER TABLE SIGNED ADD COLUMN MESSAGE TEXT;
+ALTER TABLE SIGNED ADD COLUMN SCHEME TEXT;
@@ -0,0 +1,207 @@
+use serenity::{
+    async_trait,
+    client::bridge::gateway::event::ShardStageUpdateEvent,
+    gateway::ConnectionStage,
+    model::{
+        event::ResumedEvent,
+        gateway::Ready,
+        interactions::{Interaction, InteractionApplicationCommandCallbackDataFlags, InteractionResponseType},
+    },
+    prelude::*,
+};
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use tracing::{error, field, info, info_span, Instrument, Span};
+
+use crate::chain::Eligibility;
+use crate::commands::{
+    sync::{self, Scope},
+    Registry,
+};
+use crate::config::Config;
+use crate::data::DbClient;
+use crate::diagnostics;
+use crate::health::Health;
+use crate::logging::redact_text;
+use crate::metrics::{timed, COMMANDS};
+use crate::shutdown::InFlight;
+use crate::wallet::{CommandError, ErrorKind};
+
+pub struct Handler {
+    config: Config,
+    db_client: DbClient,
+    eligibility: Eligibility,
+    commands: Registry,
+    health: Arc<Health>,
+    in_flight: Arc<InFlight>,
+    // `ready` fires again on every reconnect, the role setup only needs checking once
+    self_checked: AtomicBool,
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::ApplicationCommand(command) = interaction {
+            let span = info_span!(
+                "interaction",
+                user_id = %command.user.id,
+                guild = ?command.guild_id.map(|guild| guild.0),
+                command = %command.data.name,
+                outcome = field::Empty,
+            );
+
+            async {
+                // held until the response is sent, a shutdown waits for it
+                let guard = self.in_flight.start();
+
+                // Discord drops interactions not answered within 3 seconds, checks hitting a node or
+                // the whole SIGNED table take longer, so the reply is deferred and edited in after
+                let ephemeral = self
+                    .commands
+                    .find(&command.data.name)
+                    .map_or(false, |slash_command| slash_command.ephemeral());
+                let deferred = command.create_interaction_response(&ctx.http, |response| {
+                    response
+                        .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            if ephemeral {
+                                message.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                            }
+                            message
+                        })
+                });
+                if let Err(why) = timed("defer_interaction", deferred).await {
+                    error!(error = %why, "Cannot respond to slash command");
+                    return;
+                }
+
+                let result = match guard {
+                    Some(_) => self.commands.dispatch(&ctx, &command, self).await,
+                    None => Err(CommandError::new(
+                        ErrorKind::Unavailable,
+                        "The bot is restarting, please try again in a minute.",
+                    )),
+                };
+
+                let content = match result {
+                    Ok(reply) => {
+                        Span::current().record("outcome", &"ok");
+                        COMMANDS.with_label_values(&[&command.data.name, "ok"]).inc();
+                        info!("command succeeded");
+                        reply
+                    }
+                    Err(e) => {
+                        Span::current().record("outcome", &"error");
+                        COMMANDS
+                            .with_label_values(&[&command.data.name, e.kind.as_str()])
+                            .inc();
+                        info!(kind = e.kind.as_str(), reason = %redact_text(&e.message), "command rejected");
+                        e.message
+                    }
+                };
+
+                let response = command.edit_original_interaction_response(&ctx.http, |response| response.content(content));
+                if let Err(why) = timed("interaction_response", response).await {
+                    error!(error = %why, "Cannot respond to slash command");
+                }
+            }
+            .instrument(span)
+            .await
+        }
+    }
+
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        info!(user = %ready.user.name, "connected");
+        self.health.set_ready();
+
+        let campaigns = self.db_client.campaign_names().await;
+
+        let definitions = self.commands.definitions(&campaigns);
+        let synced = self.sync_commands(&ctx, &definitions).await;
+        self.health.set_commands_registered(synced.is_ok());
+        match synced {
+            Ok(0) => info!("slash commands are up to date"),
+            Ok(count) => info!(count, "synced slash commands"),
+            Err(why) => error!(error = %why, "could not sync slash commands"),
+        }
+
+        if !self.self_checked.swap(true, Ordering::SeqCst) {
+            diagnostics::self_check(&ctx, &self.config, ready.user.id).await;
+        }
+    }
+
+    async fn resume(&self, _ctx: Context, _: ResumedEvent) {
+        info!("gateway session resumed");
+        self.health.set_connected(true);
+    }
+
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        info!(old = ?event.old, new = ?event.new, "gateway connection stage changed");
+        self.health.set_connected(event.new == ConnectionStage::Connected);
+    }
+}
+
+impl Handler {
+    /// Only creates, updates or deletes the commands that differ from the registered ones,
+    /// returning how many changed.
+    async fn sync_commands(
+        &self,
+        ctx: &Context,
+        definitions: &[serde_json::Value],
+    ) -> Result<usize, SerenityError> {
+        let scope = Scope::from_config(&self.config);
+        let registered = timed("get_application_commands", sync::registered(&ctx.http, scope)).await?;
+        let changes = sync::plan(&registered, definitions);
+        for change in &changes {
+            info!(%change, "syncing slash command");
+            timed("sync_application_command", sync::apply(&ctx.http, scope, change)).await?;
+        }
+        Ok(changes.len())
+    }
+
+    pub fn new(config: Config, db_client: DbClient, eligibility: Eligibility) -> Self {
+        Handler {
+            config,
+            db_client,
+            eligibility,
+            commands: Registry::default(),
+            health: Arc::new(Health::new()),
+            in_flight: Arc::new(InFlight::new()),
+            self_checked: AtomicBool::new(false),
+        }
+    }
+
+    /// Shared with the shutdown, which waits for the interactions being handled.
+    pub fn in_flight(&self) -> Arc<InFlight> {
+        self.in_flight.clone()
+    }
+
+    /// Shared with the readiness probe.
+    pub fn health(&self) -> Arc<Health> {
+        self.health.clone()
+    }
+
+    pub(crate) fn db_client(&self) -> &DbClient {
+        &self.db_client
+    }
+
+    pub(crate) fn is_valid_role(&self, user_role: &str) -> bool {
+        user_role.eq(&self.config.pre_role)
+    }
+
+    pub(crate) fn post_role(&self) -> u64 {
+        self.config.post_role_id
+    }
+
+    pub(crate) fn ss58_format(&self) -> u16 {
+        self.config.ss58_format
+    }
+
+    pub(crate) fn eligibility(&self) -> &Eligibility {
+        &self.eligibility
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use ethereum_types::{H160, U256};
+use serde_json::{json, Value};
+use sp_core::{
+    crypto::AccountId32,
+    hashing::{blake2_128, twox_128},
+};
+use tracing::warn;
+use wallet_verify::decode_ss58;
+
+use crate::wallet::{CommandError, ErrorKind};
+
+/// How long a node gets to answer. Replies are deferred, so this only bounds how long a member waits
+/// for one, a hung node would otherwise leave the reply pending for good.
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An on-chain requirement a campaign places on submitted addresses.
+///
+/// RMRK items live in remarks and can only be looked up through an indexer, so only
+/// rules that a plain node can answer are supported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// The Substrate account holds at least this much free balance, in planck.
+    SubstrateBalance { min: u128 },
+    /// The EVM account owns at least this many items of an ERC-721 (or ERC-20) contract.
+    Erc721Balance { contract: String, min: u128 },
+}
+
+impl Rule {
+    fn describe(&self) -> String {
+        match self {
+            Rule::SubstrateBalance { min } => format!("a free balance of at least {} planck", min),
+            Rule::Erc721Balance { contract, min } => {
+                format!("at least {} item(s) from collection {}", min, contract)
+            }
+        }
+    }
+}
+
+/// Why a chain could not be queried.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainError {
+    /// The node did not answer in time or could not be reached, worth trying again later.
+    Unavailable(String),
+    /// Anything else, such as an error answered by the node or a malformed address.
+    Failed(String),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::Unavailable(why) | ChainError::Failed(why) => f.write_str(why),
+        }
+    }
+}
+
+/// Read access to the chains the rules are evaluated against.
+#[async_trait]
+pub trait ChainClient: Send + Sync {
+    async fn free_balance(&self, account: &AccountId32) -> Result<u128, ChainError>;
+
+    async fn erc721_balance(&self, contract: &H160, owner: &H160) -> Result<U256, ChainError>;
+}
+
+/// Talks JSON-RPC over HTTP to a Substrate node and an EVM node.
+pub struct RpcClient {
+    http: reqwest::Client,
+    substrate_url: Option<String>,
+    evm_url: Option<String>,
+}
+
+impl RpcClient {
+    pub fn new(substrate_url: Option<String>, evm_url: Option<String>) -> Self {
+        RpcClient::with_timeout(substrate_url, evm_url, RPC_TIMEOUT)
+    }
+
+    fn with_timeout(substrate_url: Option<String>, evm_url: Option<String>, timeout: Duration) -> Self {
+        RpcClient {
+            http: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .expect("Error creating HTTP client"),
+            substrate_url,
+            evm_url,
+        }
+    }
+
+    async fn request(&self, url: &Option<String>, method: &str, params: Value) -> Result<Value, ChainError> {
+        let url = match url {
+            Some(url) => url,
+            None => return Err(ChainError::Failed(format!("No RPC endpoint configured for {}", method))),
+        };
+
+        let failed = |e: reqwest::Error| {
+            let why = format!("RPC request {} failed: {}", method, e);
+            if e.is_timeout() || e.is_connect() {
+                ChainError::Unavailable(why)
+            } else {
+                ChainError::Failed(why)
+            }
+        };
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response = self.http.post(url).json(&body).send().await.map_err(failed)?;
+        let response: Value = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                failed(e)
+            } else {
+                ChainError::Failed(format!("RPC response to {} is not JSON: {}", method, e))
+            }
+        })?;
+
+        if let Some(error) = response.get("error") {
+            return Err(ChainError::Failed(format!("RPC {} returned an error: {}", method, error)));
+        }
+
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+}
+
+/// Storage key of `System::Account` for the given account.
+fn system_account_key(account: &AccountId32) -> String {
+    let account: &[u8] = account.as_ref();
+    let mut key = twox_128(b"System").to_vec();
+    key.extend_from_slice(&twox_128(b"Account"));
+    key.extend_from_slice(&blake2_128(account));
+    key.extend_from_slice(account);
+    format!("0x{}", hex::encode(key))
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, ChainError> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|_| ChainError::Failed(format!("{} is not valid hex", value)))
+}
+
+#[async_trait]
+impl ChainClient for RpcClient {
+    async fn free_balance(&self, account: &AccountId32) -> Result<u128, ChainError> {
+        let result = self
+            .request(&self.substrate_url, "state_getStorage", json!([system_account_key(account)]))
+            .await?;
+
+        // Accounts that were never funded have no storage entry.
+        let info = match result.as_str() {
+            Some(info) => decode_hex(info)?,
+            None => return Ok(0),
+        };
+
+        // AccountInfo is nonce, consumers, providers and sufficients (u32 each), then the free balance.
+        match info.get(16..32) {
+            Some(free) => Ok(u128::from_le_bytes(free.try_into().unwrap())),
+            None => Err(ChainError::Failed("Unexpected AccountInfo encoding".to_string())),
+        }
+    }
+
+    async fn erc721_balance(&self, contract: &H160, owner: &H160) -> Result<U256, ChainError> {
+        // balanceOf(address)
+        let data = format!("0x70a08231{:0>64}", hex::encode(owner.as_bytes()));
+        let result = self
+            .request(
+                &self.evm_url,
+                "eth_call",
+                json!([{ "to": format!("{:?}", contract), "data": data }, "latest"]),
+            )
+            .await?;
+
+        match result.as_str() {
+            Some(balance) => Ok(U256::from_big_endian(&decode_hex(balance)?)),
+            None => Err(ChainError::Failed("eth_call returned no data".to_string())),
+        }
+    }
+}
+
+/// Evaluates campaign rules and remembers the outcome for a while, so repeated
+/// attempts don't hit the node every time. Expired outcomes are dropped whenever a new one is
+/// stored, so the cache only holds what was checked within the last `ttl`.
+pub struct Eligibility {
+    client: Option<Box<dyn ChainClient>>,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, bool)>>,
+}
+
+impl Eligibility {
+    pub fn new(client: Option<Box<dyn ChainClient>>, ttl: Duration) -> Self {
+        Eligibility {
+            client,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks the rules that apply to the given addresses. Rules for a kind of address
+    /// that was not submitted are skipped. A node that cannot be reached is reported as
+    /// `Unavailable` rather than as an ineligible address.
+    pub async fn check(
+        &self,
+        rules: &[Rule],
+        ss58: Option<&str>,
+        h160: Option<&str>,
+    ) -> Result<(), CommandError> {
+        for rule in rules {
+            let address = match rule {
+                Rule::SubstrateBalance { .. } => ss58,
+                Rule::Erc721Balance { .. } => h160,
+            };
+            let address = match address {
+                Some(address) => address,
+                None => continue,
+            };
+
+            let holds = self.holds(rule, address).await.map_err(|e| match e {
+                ChainError::Unavailable(why) => {
+                    warn!(error = %why, "could not check campaign rule");
+                    CommandError::new(
+                        ErrorKind::Unavailable,
+                        "The chain could not be reached, please try again later.",
+                    )
+                }
+                ChainError::Failed(why) => CommandError::new(ErrorKind::Eligibility, why),
+            })?;
+            if !holds {
+                return Err(CommandError::new(
+                    ErrorKind::Eligibility,
+                    format!("Address {} does not hold {}", address, rule.describe()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn holds(&self, rule: &Rule, address: &str) -> Result<bool, ChainError> {
+        let key = format!("{:?}/{}", rule, address);
+        if let Some((at, holds)) = self.cache.lock().unwrap().get(&key) {
+            if at.elapsed() < self.ttl {
+                return Ok(*holds);
+            }
+        }
+
+        let client = match &self.client {
+            Some(client) => client,
+            None => return Err(ChainError::Failed("On-chain checks are not configured".to_string())),
+        };
+
+        let holds = match rule {
+            Rule::SubstrateBalance { min } => {
+                // any network prefix, the address was already checked against `ss58_format`
+                let account = decode_ss58(address)
+                    .map(|address| AccountId32::new(address.account))
+                    .map_err(|_| ChainError::Failed("Invalid ss58 address provided".to_string()))?;
+                client.free_balance(&account).await? >= *min
+            }
+            Rule::Erc721Balance { contract, min } => {
+                let contract = H160::from_str(contract)
+                    .map_err(|_| ChainError::Failed(format!("Invalid collection contract {}", contract)))?;
+                let owner = H160::from_str(address)
+                    .map_err(|_| ChainError::Failed("Invalid H160 address provided".to_string()))?;
+                client.erc721_balance(&contract, &owner).await? >= U256::from(*min)
+            }
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (at, _)| at.elapsed() < self.ttl);
+        cache.insert(key, (Instant::now(), holds));
+        Ok(holds)
+    }
+}
+
+
+#[tokio::test]
+async fn test_rpc_client() {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serves a single canned JSON-RPC result for every request, or never answers without one.
+    async fn mock_rpc(result: Option<Value>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut hung = Vec::new();
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let result = match &result {
+                    Some(result) => result,
+                    None => {
+                        hung.push(socket);
+                        continue;
+                    }
+                };
+                let body = json!({ "jsonrpc": "2.0", "id": 1, "result": result }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        url
+    }
+
+    let ksm = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+    let account = AccountId32::new(decode_ss58(ksm).unwrap().account);
+    let h160 = "0xb794f5ea0ba39494ce839613fffba74279579268";
+    let contract = "0x0000000000000000000000000000000000000721";
+
+    let mut info = vec![0u8; 16];
+    info.extend_from_slice(&5_000_000_000_000u128.to_le_bytes());
+    info.extend_from_slice(&[0u8; 48]);
+    let url = mock_rpc(Some(json!(format!("0x{}", hex::encode(info))))).await;
+    let client = RpcClient::new(Some(url), None);
+    assert_eq!(client.free_balance(&account).await, Ok(5_000_000_000_000));
+
+    // accounts that were never funded have no storage entry
+    let url = mock_rpc(Some(Value::Null)).await;
+    let client = RpcClient::new(Some(url), None);
+    assert_eq!(client.free_balance(&account).await, Ok(0));
+
+    let url = mock_rpc(Some(json!(format!("0x{:0>64}", "2")))).await;
+    let eligibility = Eligibility::new(
+        Some(Box::new(RpcClient::new(None, Some(url)))),
+        Duration::from_secs(60),
+    );
+    let owns_two = [Rule::Erc721Balance { contract: contract.to_string(), min: 2 }];
+    let owns_three = [Rule::Erc721Balance { contract: contract.to_string(), min: 3 }];
+    assert!(eligibility.check(&owns_two, None, Some(h160)).await.is_ok());
+    let error = eligibility.check(&owns_three, None, Some(h160)).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Eligibility);
+
+    let url = mock_rpc(None).await;
+    let client = RpcClient::with_timeout(Some(url), None, Duration::from_millis(100));
+    assert!(matches!(client.free_balance(&account).await, Err(ChainError::Unavailable(_))));
+    let eligibility = Eligibility::new(Some(Box::new(client)), Duration::from_secs(60));
+    let funded = [Rule::SubstrateBalance { min: 1 }];
+    let error = eligibility.check(&funded, Some(ksm), None).await.unwrap_err();
+    assert_eq!(error.kind, ErrorKind::Unavailable);
+}
+
+#[tokio::test]
+async fn test_results_are_cached() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    struct CountingClient {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ChainClient for CountingClient {
+        async fn free_balance(&self, _account: &AccountId32) -> Result<u128, ChainError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(10)
+        }
+
+        async fn erc721_balance(&self, _contract: &H160, _owner: &H160) -> Result<U256, ChainError> {
+            Err(ChainError::Failed("not used".to_string()))
+        }
+    }
+
+    let ksm = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+    let calls = Arc::new(AtomicUsize::new(0));
+    let eligibility = Eligibility::new(
+        Some(Box::new(CountingClient { calls: calls.clone() })),
+        Duration::from_secs(60),
+    );
+    let rules = [
+        Rule::SubstrateBalance { min: 10 },
+        Rule::Erc721Balance { contract: "0x0000000000000000000000000000000000000721".to_string(), min: 1 },
+    ];
+
+    // the ERC-721 rule is skipped as no H160 address was submitted
+    assert!(eligibility.check(&rules, Some(ksm), None).await.is_ok());
+    assert!(eligibility.check(&rules, Some(ksm), None).await.is_ok());
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // outcomes older than the ttl are dropped when another one is stored
+    let eligibility = Eligibility::new(
+        Some(Box::new(CountingClient { calls: calls.clone() })),
+        Duration::ZERO,
+    );
+    assert!(eligibility.check(&rules, Some(ksm), None).await.is_ok());
+    assert!(eligibility.check(&[Rule::SubstrateBalance { min: 5 }], Some(ksm), None).await.is_ok());
+
+    // addresses of networks substrate does not know are checked too
+    let custom = wallet_verify::encode_ss58(&decode_ss58(ksm).unwrap().account, 7391);
+    assert!(eligibility.check(&rules, Some(&custom), None).await.is_ok());
+    assert_eq!(eligibility.cache.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,323 @@
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommandOption, CreateApplicationCommands},
+    model::{
+        interactions::application_command::{
+            ApplicationCommandInteraction, ApplicationCommandInteractionDataOption,
+            ApplicationCommandOptionType,
+        },
+        Permissions,
+    },
+    prelude::*,
+};
+use serde_json::Value;
+use tracing::error;
+
+use crate::bot::Handler;
+use crate::metrics::timed;
+use crate::wallet::{CommandError, ErrorKind, Member};
+
+mod reverify;
+mod role_jobs;
+mod sign;
+pub mod sync;
+mod verify;
+mod wallet;
+
+pub use reverify::Reverify;
+pub use role_jobs::RoleJobs;
+pub use sign::Sign;
+pub use verify::Verify;
+pub use wallet::Wallet;
+
+/// A slash command: its definition, who may use it and what it does.
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Fills in the description and options, the name is set by the registry.
+    fn create(&self, command: &mut CreateApplicationCommand, campaigns: &[String]);
+
+    /// Permissions the member needs in the guild, none by default.
+    fn permissions(&self) -> Permissions {
+        Permissions::empty()
+    }
+
+    /// Whether only the member who ran the command sees the reply.
+    fn ephemeral(&self) -> bool {
+        false
+    }
+
+    /// Handles an invocation, returning the reply shown to the user.
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError>;
+}
+
+/// Every command of the bot. Both the commands sent to Discord and the dispatch come from this list.
+pub struct Registry {
+    commands: Vec<Box<dyn SlashCommand>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::new(vec![
+            Box::new(Sign),
+            Box::new(Wallet),
+            Box::new(RoleJobs),
+            Box::new(Reverify),
+            Box::new(Verify),
+        ])
+    }
+}
+
+impl Registry {
+    pub fn new(commands: Vec<Box<dyn SlashCommand>>) -> Self {
+        Registry { commands }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&dyn SlashCommand> {
+        self.commands
+            .iter()
+            .find(|command| command.name() == name)
+            .map(|command| command.as_ref())
+    }
+
+    /// The definitions sent to Discord, campaigns are offered as choices.
+    pub fn definitions(&self, campaigns: &[String]) -> Vec<Value> {
+        let mut commands = CreateApplicationCommands::default();
+        for slash_command in &self.commands {
+            commands.create_application_command(|command| {
+                slash_command.create(command.name(slash_command.name()), campaigns);
+                command
+            });
+        }
+        commands.0
+    }
+
+    /// Runs the named command after checking the member's permissions.
+    pub async fn dispatch(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError> {
+        let slash_command = match self.find(&command.data.name) {
+            Some(slash_command) => slash_command,
+            None => return Err(CommandError::new(ErrorKind::Discord, "not implemented :(")),
+        };
+
+        let granted = command
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions)
+            .unwrap_or_else(Permissions::empty);
+        if !granted.contains(slash_command.permissions()) {
+            return Err(CommandError::new(
+                ErrorKind::Role,
+                "You do not have permission to use this command.",
+            ));
+        }
+
+        slash_command.run(ctx, command, handler).await
+    }
+}
+
+/// Input of a command, parsed from its options by name.
+pub trait CommandInput: Sized {
+    fn parse(options: &Options) -> Result<Self, CommandError>;
+}
+
+/// The options of an invocation, looked up by name. Anything unexpected is reported
+/// to the user instead of panicking.
+pub struct Options<'a>(&'a [ApplicationCommandInteractionDataOption]);
+
+impl<'a> Options<'a> {
+    pub fn new(options: &'a [ApplicationCommandInteractionDataOption]) -> Self {
+        Options(options)
+    }
+
+    /// A text option, trimmed, `None` when it was left out or blank.
+    pub fn optional_string(&self, name: &str) -> Result<Option<String>, CommandError> {
+        let option = match self.0.iter().find(|option| option.name == name) {
+            Some(option) => option,
+            None => return Ok(None),
+        };
+
+        match &option.value {
+            Some(Value::String(value)) if value.trim().is_empty() => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.trim().to_string())),
+            None => Ok(None),
+            Some(_) => Err(CommandError::new(
+                ErrorKind::Input,
+                format!("The {} option must be text", name),
+            )),
+        }
+    }
+
+    /// An integer option, `None` when it was left out.
+    pub fn optional_integer(&self, name: &str) -> Result<Option<i64>, CommandError> {
+        let value = match self.0.iter().find(|option| option.name == name) {
+            Some(option) => option.value.as_ref(),
+            None => return Ok(None),
+        };
+
+        match value {
+            None => Ok(None),
+            Some(value) => value.as_i64().map(Some).ok_or_else(|| {
+                CommandError::new(ErrorKind::Input, format!("The {} option must be a whole number", name))
+            }),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Result<String, CommandError> {
+        self.optional_string(name)?.ok_or_else(|| {
+            CommandError::new(ErrorKind::Input, format!("The {} option is required", name))
+        })
+    }
+}
+
+/// Parses the options of the invocation into the command's input.
+fn input<T: CommandInput>(command: &ApplicationCommandInteraction) -> Result<T, CommandError> {
+    T::parse(&Options::new(&command.data.options))
+}
+
+// Discord accepts at most 25 choices, beyond that the campaign is typed in.
+const MAX_CAMPAIGN_CHOICES: usize = 25;
+
+fn campaign_option<'a>(
+    option: &'a mut CreateApplicationCommandOption,
+    campaigns: &[String],
+) -> &'a mut CreateApplicationCommandOption {
+    option
+        .name("campaign")
+        .description("The campaign you are registering for")
+        .kind(ApplicationCommandOptionType::String)
+        .required(true);
+    if campaigns.len() <= MAX_CAMPAIGN_CHOICES {
+        for campaign in campaigns {
+            option.add_string_choice(campaign, campaign);
+        }
+    }
+    option
+}
+
+/// The invoking member with role ids resolved to the role names campaigns refer to.
+async fn member(ctx: &Context, command: &ApplicationCommandInteraction) -> Result<Member, CommandError> {
+    let (member, guild_id) = match (&command.member, command.guild_id) {
+        (Some(member), Some(guild_id)) => (member, guild_id),
+        _ => {
+            return Err(CommandError::new(
+                ErrorKind::Input,
+                "This command can only be used in the server",
+            ))
+        }
+    };
+    let roles = match timed("guild_roles", guild_id.roles(&ctx.http)).await {
+        Ok(roles) => roles,
+        Err(why) => {
+            error!(error = %why, "could not load the guild roles");
+            return Err(CommandError::new(
+                ErrorKind::Discord,
+                "Could not load your roles, please try again",
+            ));
+        }
+    };
+    // roles unknown to the guild cannot match a campaign, so they are skipped
+    let user_roles = member
+        .roles
+        .iter()
+        .filter_map(|role_id| roles.get(role_id))
+        .map(|role| role.name.to_string())
+        .collect();
+
+    Ok(Member {
+        user_id: command.user.id.to_string(),
+        user_tag: command.user.tag(),
+        avatar: command.user.avatar_url().unwrap_or_default(),
+        roles: user_roles,
+    })
+}
+
+// keeps the reply well under Discord's 2000 characters
+const MAX_LISTED: usize = 10;
+
+/// Adds a line for each of the first items, and how many more there are when the list is cut.
+fn list_lines<T>(lines: &mut Vec<String>, items: &[T], line: impl Fn(&T) -> String) {
+    lines.extend(items.iter().take(MAX_LISTED).map(line));
+    if items.len() > MAX_LISTED {
+        lines.push(format!("and {} more", items.len() - MAX_LISTED));
+    }
+}
+
+/// Echoes the address a public key was converted to after the reply.
+fn with_converted(reply: &str, converted: Option<String>) -> String {
+    match converted {
+        Some(address) => format!("{} Your public key was recorded as {}.", reply, address),
+        None => reply.to_string(),
+    }
+}
+
+#[test]
+fn test_registry_builds_every_command() {
+    let registry = Registry::default();
+    assert!(registry.find("sign").is_some());
+    assert!(registry.find("verify").unwrap().ephemeral());
+    assert!(registry.find("rolejobs").unwrap().ephemeral());
+    assert!(registry.find("reverify").unwrap().ephemeral());
+    assert!(registry.find("claim").is_none());
+
+    let payload = Value::Array(registry.definitions(&["kanaria".to_string()]));
+    let names = payload
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|command| command["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["sign", "wallet", "rolejobs", "reverify", "verify"]);
+    assert_eq!(payload[1]["options"][1]["choices"][0]["value"], "kanaria");
+    assert_eq!(payload[1]["options"][2]["required"], false);
+}
+
+#[test]
+fn test_options_by_name() {
+    let options: Vec<ApplicationCommandInteractionDataOption> = serde_json::from_value(serde_json::json!([
+        {"name": "signature", "type": 3, "value": " 0xfb27 "},
+        {"name": "campaign", "type": 3, "value": "kanaria"},
+        {"name": "memo", "type": 3, "value": "  "},
+        {"name": "count", "type": 4, "value": 3},
+    ]))
+    .unwrap();
+    let options = Options::new(&options);
+
+    assert_eq!(options.string("signature").unwrap(), "0xfb27");
+    assert_eq!(options.optional_string("memo").unwrap(), None);
+    assert_eq!(options.optional_string("address").unwrap(), None);
+    assert_eq!(
+        options.string("address").unwrap_err().message,
+        "The address option is required"
+    );
+    assert_eq!(options.string("count").unwrap_err().kind, ErrorKind::Input);
+    assert_eq!(options.optional_integer("count").unwrap(), Some(3));
+    assert_eq!(options.optional_integer("threshold").unwrap(), None);
+    assert_eq!(
+        options.optional_integer("campaign").unwrap_err().message,
+        "The campaign option must be a whole number"
+    );
+}
+
+#[test]
+fn test_list_lines() {
+    let mut lines = vec!["header".to_string()];
+    list_lines(&mut lines, &[1, 2], |n| n.to_string());
+    assert_eq!(lines, ["header", "1", "2"]);
+
+    let mut lines = Vec::new();
+    let items: Vec<usize> = (0..MAX_LISTED + 3).collect();
+    list_lines(&mut lines, &items, |n| n.to_string());
+    assert_eq!(lines.len(), MAX_LISTED + 1);
+    assert_eq!(lines.last().unwrap(), "and 3 more");
+}
@@ -0,0 +1,60 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::{interactions::application_command::ApplicationCommandInteraction, Permissions},
+    prelude::*,
+};
+
+use super::{list_lines, SlashCommand};
+use crate::bot::Handler;
+use crate::wallet::{self, CommandError, ErrorKind, Reverification};
+
+/// `/reverify`: checks every stored signature again and reports the ones that fail.
+pub struct Reverify;
+
+#[async_trait]
+impl SlashCommand for Reverify {
+    fn name(&self) -> &'static str {
+        "reverify"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand, _campaigns: &[String]) {
+        command.description("Verify every stored signature again");
+    }
+
+    fn permissions(&self) -> Permissions {
+        Permissions::ADMINISTRATOR
+    }
+
+    fn ephemeral(&self) -> bool {
+        true
+    }
+
+    async fn run(
+        &self,
+        _ctx: &Context,
+        _command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError> {
+        let report = wallet::reverify(handler.db_client())
+            .await
+            .map_err(|e| CommandError::new(ErrorKind::Storage, e))?;
+        Ok(describe(&report))
+    }
+}
+
+fn describe(report: &Reverification) -> String {
+    let mut lines = vec![format!(
+        "{} signatures verified, {} without a stored signature, {} mismatches",
+        report.verified,
+        report.unproven,
+        report.mismatches.len()
+    )];
+    list_lines(&mut lines, &report.mismatches, |mismatch| {
+        format!(
+            "user {} {} {}: {}",
+            mismatch.user_id, mismatch.campaign, mismatch.ksm_address, mismatch.reason
+        )
+    });
+    lines.join("\n")
+}
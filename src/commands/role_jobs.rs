@@ -0,0 +1,107 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::{
+        interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType},
+        Permissions,
+    },
+    prelude::*,
+};
+
+use super::{input, list_lines, CommandInput, Options, SlashCommand};
+use crate::bot::Handler;
+use crate::data::RoleJob;
+use crate::wallet::{CommandError, ErrorKind};
+
+pub struct RoleJobsRequest {
+    pub retry: bool,
+}
+
+impl CommandInput for RoleJobsRequest {
+    fn parse(options: &Options) -> Result<Self, CommandError> {
+        match options.optional_string("action")?.as_deref() {
+            None | Some("list") => Ok(RoleJobsRequest { retry: false }),
+            Some("retry") => Ok(RoleJobsRequest { retry: true }),
+            Some(other) => Err(CommandError::new(
+                ErrorKind::Input,
+                format!("Unknown action {}", other),
+            )),
+        }
+    }
+}
+
+/// `/rolejobs`: lists the role grants that failed for good, or queues them again.
+pub struct RoleJobs;
+
+#[async_trait]
+impl SlashCommand for RoleJobs {
+    fn name(&self) -> &'static str {
+        "rolejobs"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand, _campaigns: &[String]) {
+        command
+            .description("Inspect or retry failed role grants")
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("List the failed jobs (default) or queue them again")
+                    .kind(ApplicationCommandOptionType::String)
+                    .required(false)
+                    .add_string_choice("list", "list")
+                    .add_string_choice("retry", "retry")
+            });
+    }
+
+    fn permissions(&self) -> Permissions {
+        Permissions::MANAGE_ROLES
+    }
+
+    fn ephemeral(&self) -> bool {
+        true
+    }
+
+    async fn run(
+        &self,
+        _ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError> {
+        let request = input::<RoleJobsRequest>(command)?;
+        let db_client = handler.db_client();
+
+        if request.retry {
+            let count = db_client
+                .retry_failed_role_jobs()
+                .await
+                .map_err(|e| CommandError::new(ErrorKind::Storage, e))?;
+            return Ok(format!("Queued {} failed role jobs again.", count));
+        }
+
+        let jobs = db_client
+            .failed_role_jobs()
+            .await
+            .map_err(|e| CommandError::new(ErrorKind::Storage, e))?;
+        Ok(describe(&jobs))
+    }
+}
+
+fn describe(jobs: &[RoleJob]) -> String {
+    if jobs.is_empty() {
+        return "No failed role jobs.".to_string();
+    }
+
+    let mut lines = vec![format!("{} failed role jobs:", jobs.len())];
+    list_lines(&mut lines, jobs, |job| {
+        format!(
+            "#{} {} role {} for user {} after {} attempts: {}",
+            job.id,
+            job.action.as_str(),
+            job.role_id,
+            job.user_id,
+            job.attempts,
+            job.last_error.as_deref().unwrap_or("unknown error")
+        )
+    });
+    lines.join("\n")
+}
@@ -0,0 +1,123 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType},
+    prelude::*,
+};
+
+use super::{campaign_option, input, member, with_converted, CommandInput, Options, SlashCommand};
+use crate::bot::Handler;
+use crate::wallet::{self, CommandError, ErrorKind, Multisig, SignRequest};
+
+// const ERROR_POSTFIX: &str = ". Follow the guide here <some link>";
+const ERROR_POSTFIX: &str = "";
+
+impl CommandInput for SignRequest {
+    fn parse(options: &Options) -> Result<Self, CommandError> {
+        let signatories = options.optional_string("signatories")?;
+        let threshold = options.optional_integer("threshold")?;
+        let multisig = match (signatories, threshold) {
+            (None, None) => None,
+            (Some(signatories), Some(threshold)) => Some(Multisig {
+                signatories: signatories
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                threshold: u16::try_from(threshold).map_err(|_| {
+                    CommandError::new(ErrorKind::Input, "The threshold option is out of range")
+                })?,
+            }),
+            _ => {
+                return Err(CommandError::new(
+                    ErrorKind::Input,
+                    "A multisig account needs both the signatories and the threshold options",
+                ))
+            }
+        };
+
+        Ok(SignRequest {
+            kusama_address: options.string("kusama_address")?,
+            moonbeam_address: options.string("moonbeam_address")?,
+            signature: options.string("signature")?,
+            campaign: options.string("campaign")?,
+            multisig,
+        })
+    }
+}
+
+/// `/sign`: links a Kusama and a Moonbeam wallet with a signature of the latter by the former,
+/// or by one of the signatories of a multisig Kusama account.
+pub struct Sign;
+
+#[async_trait]
+impl SlashCommand for Sign {
+    fn name(&self) -> &'static str {
+        "sign"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand, campaigns: &[String]) {
+        command.description("Register and verify wallet");
+        sign_options(command, campaigns);
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError> {
+        let mut request = input::<SignRequest>(command)?;
+        let converted = request.normalize(handler.ss58_format());
+        let member = member(ctx, command).await?;
+
+        match wallet::sign(handler, &member, request).await {
+            Ok(()) => Ok(with_converted("Your details have been recorded.", converted)),
+            Err(mut e) => {
+                e.message = format!("{} {}", e.message, ERROR_POSTFIX);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// The options of `/sign`, shared with `/verify` which takes the same details.
+pub(super) fn sign_options(command: &mut CreateApplicationCommand, campaigns: &[String]) {
+    command
+        .create_option(|option| {
+            option
+                .name("kusama_address")
+                .description("Kusama wallet address")
+                .kind(ApplicationCommandOptionType::String)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("moonbeam_address")
+                .description("Moonbeam wallet address")
+                .kind(ApplicationCommandOptionType::String)
+                .required(true)
+        })
+        .create_option(|option| {
+            option
+                .name("signature")
+                .description("Your GLMR address signed by your KSM account, or your KSM address signed with MetaMask")
+                .kind(ApplicationCommandOptionType::String)
+                .required(true)
+        })
+        .create_option(|option| campaign_option(option, campaigns))
+        .create_option(|option| {
+            option
+                .name("signatories")
+                .description("For a multisig Kusama address: all its signatories, separated by commas")
+                .kind(ApplicationCommandOptionType::String)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("threshold")
+                .description("For a multisig Kusama address: the number of approvals it needs")
+                .kind(ApplicationCommandOptionType::Integer)
+                .required(false)
+        });
+}
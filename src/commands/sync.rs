@@ -0,0 +1,177 @@
+use std::fmt;
+
+use serde_json::{json, Value};
+use serenity::{http::Http, model::interactions::application_command::ApplicationCommand, Error};
+
+use crate::config::Config;
+
+/// Where the commands are registered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Guild(u64),
+    /// Available in every guild and in DMs, Discord may take up to an hour to propagate changes.
+    Global,
+}
+
+impl Scope {
+    pub fn from_config(config: &Config) -> Self {
+        if config.global_commands {
+            Scope::Global
+        } else {
+            Scope::Guild(config.guild_id)
+        }
+    }
+}
+
+/// A difference between the registered commands and the ones the bot defines.
+#[derive(Debug, PartialEq)]
+pub enum Change {
+    Create { name: String, definition: Value },
+    Update { id: u64, name: String, definition: Value },
+    Delete { id: u64, name: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Create { name, .. } => write!(f, "create /{}", name),
+            Change::Update { name, id, .. } => write!(f, "update /{} ({})", name, id),
+            Change::Delete { name, id } => write!(f, "delete /{} ({})", name, id),
+        }
+    }
+}
+
+/// Changes turning the `existing` commands into the `desired` definitions, matched by name.
+pub fn plan(existing: &[ApplicationCommand], desired: &[Value]) -> Vec<Change> {
+    let mut changes = vec![];
+    for definition in desired {
+        let name = definition["name"].as_str().unwrap_or_default().to_string();
+        match existing.iter().find(|command| command.name == name) {
+            None => changes.push(Change::Create {
+                name,
+                definition: definition.clone(),
+            }),
+            Some(command) => {
+                let registered = serde_json::to_value(command).unwrap_or_default();
+                if canonical(&registered) != canonical(definition) {
+                    changes.push(Change::Update {
+                        id: command.id.0,
+                        name,
+                        definition: definition.clone(),
+                    });
+                }
+            }
+        }
+    }
+    for command in existing {
+        if !desired.iter().any(|definition| definition["name"] == command.name.as_str()) {
+            changes.push(Change::Delete {
+                id: command.id.0,
+                name: command.name.clone(),
+            });
+        }
+    }
+    changes
+}
+
+/// The fields the bot sets, with Discord's defaults filled in, so a definition compares
+/// equal to the command Discord returns for it.
+fn canonical(command: &Value) -> Value {
+    json!({
+        "name": command["name"],
+        "description": command["description"],
+        "default_permission": command["default_permission"].as_bool().unwrap_or(true),
+        "options": canonical_options(&command["options"]),
+    })
+}
+
+fn canonical_options(options: &Value) -> Value {
+    let options = options.as_array().cloned().unwrap_or_default();
+    Value::Array(
+        options
+            .iter()
+            .map(|option| {
+                let choices = option["choices"].as_array().cloned().unwrap_or_default();
+                json!({
+                    "type": option["type"],
+                    "name": option["name"],
+                    "description": option["description"],
+                    "required": option["required"].as_bool().unwrap_or(false),
+                    "choices": choices
+                        .iter()
+                        .map(|choice| json!({ "name": choice["name"], "value": choice["value"] }))
+                        .collect::<Vec<_>>(),
+                    "options": canonical_options(&option["options"]),
+                })
+            })
+            .collect(),
+    )
+}
+
+pub async fn registered(http: &Http, scope: Scope) -> Result<Vec<ApplicationCommand>, Error> {
+    match scope {
+        Scope::Guild(guild_id) => http.get_guild_application_commands(guild_id).await,
+        Scope::Global => http.get_global_application_commands().await,
+    }
+}
+
+pub async fn apply(http: &Http, scope: Scope, change: &Change) -> Result<(), Error> {
+    match (change, scope) {
+        (Change::Create { definition, .. }, Scope::Guild(guild_id)) => http
+            .create_guild_application_command(guild_id, definition)
+            .await
+            .map(|_| ()),
+        (Change::Create { definition, .. }, Scope::Global) => {
+            http.create_global_application_command(definition).await.map(|_| ())
+        }
+        (Change::Update { id, definition, .. }, Scope::Guild(guild_id)) => http
+            .edit_guild_application_command(guild_id, *id, definition)
+            .await
+            .map(|_| ()),
+        (Change::Update { id, definition, .. }, Scope::Global) => http
+            .edit_global_application_command(*id, definition)
+            .await
+            .map(|_| ()),
+        (Change::Delete { id, .. }, Scope::Guild(guild_id)) => {
+            http.delete_guild_application_command(guild_id, *id).await
+        }
+        (Change::Delete { id, .. }, Scope::Global) => http.delete_global_application_command(*id).await,
+    }
+}
+
+#[test]
+fn test_plan_only_touches_changed_commands() {
+    let desired = super::Registry::default().definitions(&["kanaria".to_string()]);
+    let registered = |id: u64, definition: &Value| -> ApplicationCommand {
+        let mut command = definition.clone();
+        command["id"] = json!(id.to_string());
+        command["application_id"] = json!("1");
+        command["version"] = json!("1");
+        command["type"] = json!(1);
+        serde_json::from_value(command).unwrap()
+    };
+
+    let existing = desired
+        .iter()
+        .enumerate()
+        .map(|(i, definition)| registered(10 + i as u64, definition))
+        .collect::<Vec<_>>();
+    assert_eq!(plan(&existing, &desired), vec![]);
+
+    let mut renamed = desired[1].clone();
+    renamed["description"] = json!("Old description");
+    let mut stale = desired[0].clone();
+    stale["name"] = json!("kanaria");
+    let existing = vec![
+        registered(11, &renamed),
+        registered(12, &stale),
+        registered(13, &desired[2]),
+        registered(14, &desired[3]),
+        registered(15, &desired[4]),
+    ];
+    let changes = plan(&existing, &desired);
+    assert_eq!(
+        changes.iter().map(|change| change.to_string()).collect::<Vec<_>>(),
+        vec!["create /sign", "update /wallet (11)", "delete /kanaria (12)"]
+    );
+}
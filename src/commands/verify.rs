@@ -0,0 +1,63 @@
+use serenity::{
+    async_trait, builder::CreateApplicationCommand,
+    model::interactions::application_command::ApplicationCommandInteraction, prelude::*,
+};
+
+use super::{input, member, sign::sign_options, SlashCommand};
+use crate::bot::Handler;
+use crate::wallet::{self, Check, CommandError, SignRequest};
+
+/// `/verify`: runs the `/sign` checks without recording anything and replies, to the member only,
+/// with the outcome of each step.
+pub struct Verify;
+
+#[async_trait]
+impl SlashCommand for Verify {
+    fn name(&self) -> &'static str {
+        "verify"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand, campaigns: &[String]) {
+        command.description("Check your wallet details and signature without recording them");
+        sign_options(command, campaigns);
+    }
+
+    fn ephemeral(&self) -> bool {
+        true
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError> {
+        let mut request = input::<SignRequest>(command)?;
+        let converted = request.normalize(handler.ss58_format());
+        let member = member(ctx, command).await?;
+
+        let checks = wallet::dry_run(handler, &member, request).await;
+        Ok(match converted {
+            Some(address) => format!("Your public key is the address {}\n{}", address, checklist(&checks)),
+            None => checklist(&checks),
+        })
+    }
+}
+
+fn checklist(checks: &[Check]) -> String {
+    let mut lines: Vec<String> = checks
+        .iter()
+        .map(|check| match &check.result {
+            Some(Ok(detail)) if detail.is_empty() => format!("✅ {}", check.step),
+            Some(Ok(detail)) => format!("✅ {}: {}", check.step, detail),
+            Some(Err(why)) => format!("❌ {}: {}", check.step, why),
+            None => format!("➖ {}: skipped", check.step),
+        })
+        .collect();
+    if checks.iter().all(Check::passed) {
+        lines.push("Everything checks out, `/sign` will record these details.".to_string());
+    } else {
+        lines.push("Fix the failed steps before using `/sign`.".to_string());
+    }
+    lines.join("\n")
+}
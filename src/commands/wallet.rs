@@ -0,0 +1,71 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::interactions::application_command::{ApplicationCommandInteraction, ApplicationCommandOptionType},
+    prelude::*,
+};
+
+use super::{campaign_option, input, member, with_converted, CommandInput, Options, SlashCommand};
+use crate::bot::Handler;
+use crate::wallet::{self, CommandError, RegisterRequest, Registered};
+
+impl CommandInput for RegisterRequest {
+    fn parse(options: &Options) -> Result<Self, CommandError> {
+        Ok(RegisterRequest {
+            address_type: options.optional_string("type")?,
+            address: options.string("address")?,
+            campaign: options.string("campaign")?,
+        })
+    }
+}
+
+/// `/wallet`: registers a single wallet, the post role is granted for Kusama wallets by the role worker.
+pub struct Wallet;
+
+#[async_trait]
+impl SlashCommand for Wallet {
+    fn name(&self) -> &'static str {
+        "wallet"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand, campaigns: &[String]) {
+        // Discord wants the required options first
+        command
+            .description("Register user wallet")
+            .create_option(|option| {
+                option
+                    .name("address")
+                    .description("The wallet address")
+                    .kind(ApplicationCommandOptionType::String)
+                    .required(true)
+            })
+            .create_option(|option| campaign_option(option, campaigns))
+            .create_option(|option| {
+                option
+                    .name("type")
+                    .description("Type of wallet, detected from the address when left out")
+                    .kind(ApplicationCommandOptionType::String)
+                    .required(false)
+                    .add_string_choice("Kusama", "Kusama")
+                    .add_string_choice("Moonbeam", "Moonbeam")
+                    .add_string_choice("Moonriver", "Moonriver")
+            });
+    }
+
+    async fn run(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        handler: &Handler,
+    ) -> Result<String, CommandError> {
+        let mut request = input::<RegisterRequest>(command)?;
+        let converted = request.normalize(handler.ss58_format());
+        let member = member(ctx, command).await?;
+
+        let reply = match wallet::register(handler, &member, request).await? {
+            Registered::Recorded => "Your details have been recorded.",
+            Registered::GrantRole(_) => "Your details have been recorded, your new role will be applied shortly.",
+        };
+        Ok(with_converted(reply, converted))
+    }
+}
@@ -0,0 +1,413 @@
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    net::SocketAddr,
+    path::Path,
+};
+
+use toml::value::{Table, Value};
+
+/// Settings of the bot, read from a TOML file. Every key can be overridden by an environment
+/// variable named after the upper-cased key, e.g. `guild_id` by `GUILD_ID`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub discord_token: String,
+    pub application_id: u64,
+    pub guild_id: u64,
+    /// Register the slash commands globally instead of in `guild_id` only.
+    pub global_commands: bool,
+    pub database_url: String,
+    /// Name of the role a user needs before they can use `/wallet`.
+    pub pre_role: String,
+    /// Id of the role a user is given after registering a Kusama wallet.
+    pub post_role_id: u64,
+    /// SS58 network prefix public keys given as Kusama addresses are converted to.
+    pub ss58_format: u16,
+    /// Channel the startup self-check reports problems to.
+    pub admin_channel_id: Option<u64>,
+    pub substrate_rpc_url: Option<String>,
+    pub evm_rpc_url: Option<String>,
+    pub rule_cache_secs: u64,
+    /// How long a shutdown waits for the interactions being handled.
+    pub shutdown_timeout_secs: u64,
+    pub dashboard_addr: Option<SocketAddr>,
+    pub dashboard_token: Option<String>,
+    /// Where `/metrics`, `/healthz` and `/readyz` are served, disabled when unset.
+    pub metrics_addr: Option<SocketAddr>,
+    /// Where only `/healthz` and `/readyz` are served, independently of `metrics_addr`.
+    pub health_addr: Option<SocketAddr>,
+    /// Log filter, e.g. `info` or `exosama_wallet_collector=debug,serenity=warn`.
+    pub log_level: String,
+    pub log_format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Every problem found in the configuration, so they can all be fixed in one go.
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+const DEFAULT_RULE_CACHE_SECS: u64 = 300;
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_LOG_LEVEL: &str = "info";
+const DEFAULT_SS58_FORMAT: u64 = 2;
+// two byte SS58 prefixes hold 14 bits
+const MAX_SS58_FORMAT: u64 = 16383;
+
+impl Config {
+    /// Loads the file named by `CONFIG_FILE`, or `config.toml` when it exists, and applies
+    /// the environment on top. A missing default file is fine, everything can come from the environment.
+    pub fn load() -> Result<Config, ConfigError> {
+        let contents = match dotenv::var("CONFIG_FILE") {
+            Ok(path) => match fs::read_to_string(&path) {
+                Ok(contents) => Some(contents),
+                Err(e) => return Err(ConfigError(vec![format!("Cannot read {}: {}", path, e)])),
+            },
+            Err(_) => fs::read_to_string(DEFAULT_CONFIG_FILE).ok(),
+        };
+
+        Config::parse(contents.as_deref(), |key| dotenv::var(key).ok())
+    }
+
+    pub fn parse(file: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        let table = match file.map(toml::from_str::<Table>) {
+            Some(Ok(table)) => table,
+            Some(Err(e)) => return Err(ConfigError(vec![format!("Config file is not valid TOML: {}", e)])),
+            None => Table::new(),
+        };
+        let mut source = Source {
+            table,
+            env: &env,
+            errors: vec![],
+        };
+
+        let discord_token = source.required("discord_token");
+        let application_id = source.id("application_id");
+        let guild_id = source.id("guild_id");
+        let global_commands = source.flag("global_commands");
+        // db_file is still honoured for SQLite setups that predate database_url
+        let database_url = match source.get("database_url").or_else(|| source.get("db_file")) {
+            Some(url) => url,
+            None => {
+                source.errors.push("database_url is required".to_string());
+                String::new()
+            }
+        };
+        if !database_url.is_empty() {
+            if let Err(e) = check_database(&database_url) {
+                source.errors.push(e);
+            }
+        }
+        let pre_role = source.required("pre_role");
+        let post_role_id = source.id("post_role_id");
+        let ss58_format = source.number("ss58_format", DEFAULT_SS58_FORMAT);
+        if ss58_format > MAX_SS58_FORMAT {
+            source.errors.push(format!(
+                "ss58_format must be an SS58 network prefix up to {}, got {}",
+                MAX_SS58_FORMAT, ss58_format
+            ));
+        }
+        let admin_channel_id = source.optional_id("admin_channel_id");
+        let substrate_rpc_url = source.url("substrate_rpc_url");
+        let evm_rpc_url = source.url("evm_rpc_url");
+        let rule_cache_secs = source.number("rule_cache_secs", DEFAULT_RULE_CACHE_SECS);
+        let shutdown_timeout_secs = source.number("shutdown_timeout_secs", DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+        let dashboard_addr = source.socket_addr("dashboard_addr");
+        let dashboard_token = source.get("dashboard_token");
+        if dashboard_addr.is_some() && dashboard_token.is_none() {
+            source
+                .errors
+                .push("dashboard_token is required when dashboard_addr is set".to_string());
+        }
+
+        let metrics_addr = source.socket_addr("metrics_addr");
+        let health_addr = source.socket_addr("health_addr");
+        if health_addr.is_some() && health_addr == metrics_addr {
+            source
+                .errors
+                .push("health_addr must differ from metrics_addr, which already serves the probes".to_string());
+        }
+
+        let log_level = source
+            .get("log_level")
+            .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string());
+        if tracing_subscriber::EnvFilter::try_new(&log_level).is_err() {
+            source
+                .errors
+                .push(format!("log_level {} is not a valid filter", log_level));
+        }
+        let log_format = match source.get("log_format").as_deref() {
+            None | Some("text") => LogFormat::Text,
+            Some("json") => LogFormat::Json,
+            Some(other) => {
+                source
+                    .errors
+                    .push(format!("log_format must be text or json, got {}", other));
+                LogFormat::Text
+            }
+        };
+
+        if !source.errors.is_empty() {
+            return Err(ConfigError(source.errors));
+        }
+
+        Ok(Config {
+            discord_token,
+            application_id,
+            guild_id,
+            global_commands,
+            database_url,
+            pre_role,
+            post_role_id,
+            ss58_format: ss58_format as u16,
+            admin_channel_id,
+            substrate_rpc_url,
+            evm_rpc_url,
+            rule_cache_secs,
+            shutdown_timeout_secs,
+            dashboard_addr,
+            dashboard_token,
+            metrics_addr,
+            health_addr,
+            log_level,
+            log_format,
+        })
+    }
+}
+
+struct Source<'a> {
+    table: Table,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    errors: Vec<String>,
+}
+
+impl Source<'_> {
+    fn get(&self, key: &str) -> Option<String> {
+        (self.env)(&key.to_uppercase())
+            .or_else(|| {
+                self.table.get(key).map(|value| match value {
+                    Value::String(s) => s.to_string(),
+                    other => other.to_string(),
+                })
+            })
+            .filter(|value| !value.trim().is_empty())
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        match self.get(key) {
+            Some(value) => value,
+            None => {
+                self.errors.push(format!("{} is required", key));
+                String::new()
+            }
+        }
+    }
+
+    /// Discord ids (snowflakes) are non-zero 64 bit integers.
+    fn id(&mut self, key: &str) -> u64 {
+        let value = self.required(key);
+        if value.is_empty() {
+            return 0;
+        }
+        self.parse_id(key, &value).unwrap_or(0)
+    }
+
+    fn optional_id(&mut self, key: &str) -> Option<u64> {
+        let value = self.get(key)?;
+        self.parse_id(key, &value)
+    }
+
+    fn parse_id(&mut self, key: &str, value: &str) -> Option<u64> {
+        match value.parse::<u64>() {
+            Ok(id) if id > 0 => Some(id),
+            _ => {
+                self.errors
+                    .push(format!("{} must be a Discord id, got {}", key, value));
+                None
+            }
+        }
+    }
+
+    fn number(&mut self, key: &str, default: u64) -> u64 {
+        match self.get(key).map(|value| (value.parse(), value)) {
+            None => default,
+            Some((Ok(number), _)) => number,
+            Some((Err(_), value)) => {
+                self.errors
+                    .push(format!("{} must be a positive integer, got {}", key, value));
+                default
+            }
+        }
+    }
+
+    fn flag(&mut self, key: &str) -> bool {
+        match self.get(key).as_deref() {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(value) => {
+                self.errors
+                    .push(format!("{} must be true or false, got {}", key, value));
+                false
+            }
+        }
+    }
+
+    fn socket_addr(&mut self, key: &str) -> Option<SocketAddr> {
+        let addr = self.get(key)?;
+        match addr.parse() {
+            Ok(addr) => Some(addr),
+            Err(_) => {
+                self.errors
+                    .push(format!("{} {} is not a socket address", key, addr));
+                None
+            }
+        }
+    }
+
+    fn url(&mut self, key: &str) -> Option<String> {
+        let url = self.get(key)?;
+        if url.starts_with("http://") || url.starts_with("https://") {
+            Some(url)
+        } else {
+            self.errors
+                .push(format!("{} must be an http(s) URL, got {}", key, url));
+            None
+        }
+    }
+}
+
+/// Postgres is checked when connecting, a SQLite file must be writable or creatable.
+fn check_database(url: &str) -> Result<(), String> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        return Ok(());
+    }
+
+    let filename = url.strip_prefix("sqlite:").unwrap_or(url);
+    let filename = filename.strip_prefix("//").unwrap_or(filename);
+    if filename == ":memory:" {
+        return Ok(());
+    }
+
+    let path = Path::new(filename);
+    if path.exists() {
+        return OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map(|_| ())
+            .map_err(|e| format!("Database file {} is not writable: {}", filename, e));
+    }
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    match fs::metadata(dir) {
+        Ok(meta) if meta.is_dir() && !meta.permissions().readonly() => Ok(()),
+        _ => Err(format!(
+            "Database file {} cannot be created in {}",
+            filename,
+            dir.display()
+        )),
+    }
+}
+
+#[test]
+fn test_config_env_overrides_file() {
+    let file = r#"
+        discord_token = "token"
+        application_id = 1
+        guild_id = "2"
+        global_commands = true
+        database_url = "sqlite::memory:"
+        pre_role = "Verified"
+        post_role_id = 3
+    "#;
+    let config = Config::parse(Some(file), |key| match key {
+        "GUILD_ID" => Some("20".to_string()),
+        "RULE_CACHE_SECS" => Some("60".to_string()),
+        _ => None,
+    })
+    .unwrap();
+
+    assert_eq!(config.application_id, 1);
+    assert_eq!(config.guild_id, 20);
+    assert!(config.global_commands);
+    assert_eq!(config.post_role_id, 3);
+    assert_eq!(config.rule_cache_secs, 60);
+    assert_eq!(config.substrate_rpc_url, None);
+    assert_eq!(config.log_format, LogFormat::Text);
+    assert_eq!(config.ss58_format, 2);
+}
+
+#[test]
+fn test_config_reports_every_problem() {
+    let file = r#"
+        application_id = "app"
+        database_url = "sqlite::memory:"
+        post_role_id = "Member"
+        ss58_format = 20000
+        evm_rpc_url = "localhost:9933"
+        dashboard_addr = "127.0.0.1:8080"
+        metrics_addr = "9090"
+        log_format = "yaml"
+    "#;
+    let errors = Config::parse(Some(file), |_| None).unwrap_err().0;
+
+    assert_eq!(
+        errors,
+        vec![
+            "discord_token is required",
+            "application_id must be a Discord id, got app",
+            "guild_id is required",
+            "pre_role is required",
+            "post_role_id must be a Discord id, got Member",
+            "ss58_format must be an SS58 network prefix up to 16383, got 20000",
+            "evm_rpc_url must be an http(s) URL, got localhost:9933",
+            "dashboard_token is required when dashboard_addr is set",
+            "metrics_addr 9090 is not a socket address",
+            "log_format must be text or json, got yaml",
+        ]
+    );
+}
+
+#[test]
+fn test_config_health_addr() {
+    let file = r#"
+        discord_token = "token"
+        application_id = 1
+        guild_id = 2
+        database_url = "sqlite::memory:"
+        pre_role = "Verified"
+        post_role_id = 3
+        health_addr = "0.0.0.0:8081"
+    "#;
+    let config = Config::parse(Some(file), |_| None).unwrap();
+    assert_eq!(config.health_addr, Some("0.0.0.0:8081".parse().unwrap()));
+    assert_eq!(config.metrics_addr, None);
+
+    let errors = Config::parse(Some(file), |key| match key {
+        "METRICS_ADDR" => Some("0.0.0.0:8081".to_string()),
+        _ => None,
+    })
+    .unwrap_err()
+    .0;
+    assert_eq!(errors, vec!["health_addr must differ from metrics_addr, which already serves the probes"]);
+}
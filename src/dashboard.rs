@@ -0,0 +1,270 @@
+use std::{collections::HashMap, convert::Infallible, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde::Serialize;
+use serde_json::json;
+use subtle::ConstantTimeEq;
+use tracing::{error, info};
+
+use crate::data::DbClient;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+/// A page of results, `number` starts at 1.
+#[derive(Debug, PartialEq)]
+struct Page {
+    number: i64,
+    size: i64,
+    offset: i64,
+}
+
+struct State {
+    db_client: DbClient,
+    token: String,
+}
+
+/// Serves the dashboard until the process exits. Every request must carry the token,
+/// either as a bearer token or as the password of HTTP basic auth so browsers can log in.
+pub async fn serve(addr: SocketAddr, db_client: DbClient, token: String) {
+    let state = Arc::new(State { db_client, token });
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle(&state, request).await) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(why) => {
+            error!(%addr, error = %why, "Cannot start dashboard");
+            return;
+        }
+    };
+
+    info!("Dashboard listening on http://{}", addr);
+    if let Err(why) = server.serve(make_service).await {
+        error!(error = %why, "Dashboard error");
+    }
+}
+
+async fn handle(state: &State, request: Request<Body>) -> Response<Body> {
+    if !is_authorized(&state.token, &request) {
+        return Response::builder()
+            .status(StatusCode::UNAUTHORIZED)
+            .header(header::WWW_AUTHENTICATE, "Basic realm=\"dashboard\"")
+            .body(Body::empty())
+            .unwrap();
+    }
+
+    if request.method() != Method::GET {
+        return error(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported");
+    }
+
+    let query: HashMap<String, String> = request
+        .uri()
+        .query()
+        .map(|q| form_urlencoded::parse(q.as_bytes()).into_owned().collect())
+        .unwrap_or_default();
+    let campaign = query.get("campaign").cloned();
+    let page = match pagination(&query) {
+        Ok(page) => page,
+        Err(why) => return error(StatusCode::BAD_REQUEST, why),
+    };
+
+    let db_client = &state.db_client;
+    match request.uri().path() {
+        "/" => Response::builder()
+            .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Body::from(INDEX_HTML))
+            .unwrap(),
+        "/api/signed" => paged(db_client.signed_page(campaign, page.size, page.offset).await, &page),
+        "/api/registrations" => paged(
+            db_client
+                .registration_page(campaign, query.get("chain").cloned(), page.size, page.offset)
+                .await,
+            &page,
+        ),
+        "/api/stats" => to_json(db_client.campaign_stats().await),
+        "/api/whitelist" => match (campaign, query.get("address")) {
+            (Some(campaign), Some(address)) => {
+                to_json(db_client.whitelist_status(campaign, address.to_string()).await)
+            }
+            _ => error(StatusCode::BAD_REQUEST, "campaign and address are required"),
+        },
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+fn is_authorized(token: &str, request: &Request<Body>) -> bool {
+    let value = match request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(value) => value,
+        None => return false,
+    };
+
+    if let Some(bearer) = value.strip_prefix("Bearer ") {
+        return token_matches(token, bearer);
+    }
+
+    value
+        .strip_prefix("Basic ")
+        .and_then(|credentials| base64::decode(credentials).ok())
+        .and_then(|credentials| String::from_utf8(credentials).ok())
+        .and_then(|credentials| {
+            credentials
+                .split_once(':')
+                .map(|(_, password)| token_matches(token, password))
+        })
+        .unwrap_or(false)
+}
+
+/// Compares in constant time, so response timing does not reveal how much of the token was guessed.
+fn token_matches(token: &str, candidate: &str) -> bool {
+    token.as_bytes().ct_eq(candidate.as_bytes()).into()
+}
+
+/// Pages start at 1, page sizes are capped so a single request can't dump the whole table.
+fn pagination(query: &HashMap<String, String>) -> Result<Page, &'static str> {
+    let positive = |key: &str, default: i64| match query.get(key) {
+        None => Some(default),
+        Some(value) => value.parse::<i64>().ok().filter(|n| *n > 0),
+    };
+    let number = positive("page", 1).ok_or("page must be a positive number")?;
+    let size = positive("per_page", DEFAULT_PAGE_SIZE)
+        .ok_or("per_page must be a positive number")?
+        .min(MAX_PAGE_SIZE);
+    let offset = (number - 1).checked_mul(size).ok_or("page is out of range")?;
+    Ok(Page { number, size, offset })
+}
+
+fn paged<T: Serialize>(result: Result<(Vec<T>, i64), String>, page: &Page) -> Response<Body> {
+    to_json(result.map(|(items, total)| {
+        json!({ "items": items, "page": page.number, "per_page": page.size, "total": total })
+    }))
+}
+
+fn to_json<T: Serialize>(result: Result<T, String>) -> Response<Body> {
+    match result {
+        Ok(value) => Response::builder()
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_string(&value).unwrap()))
+            .unwrap(),
+        Err(why) => {
+            error!(error = %why, "Dashboard query failed");
+            error(StatusCode::INTERNAL_SERVER_ERROR, "Query failed")
+        }
+    }
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "error": message }).to_string()))
+        .unwrap()
+}
+
+const INDEX_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Wallet collector</title>
+<style>
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+</style>
+</head>
+<body>
+<h1>Campaigns</h1>
+<table id="stats"><tr><th>Campaign</th><th>Signed</th><th>Registrations</th><th>Whitelisted</th><th>Whitelisted &amp; signed</th></tr></table>
+<h1>Signed users</h1>
+<p>
+<label>Campaign <input id="campaign"></label>
+<button onclick="load(1)">Show</button>
+<button onclick="load(page - 1)">Previous</button>
+<button onclick="load(page + 1)">Next</button>
+<span id="position"></span>
+</p>
+<table id="signed"></table>
+<script>
+let page = 1;
+function cell(row, value) { row.insertCell().textContent = value ?? ""; }
+async function stats() {
+  const table = document.getElementById("stats");
+  for (const c of await (await fetch("api/stats")).json()) {
+    const row = table.insertRow();
+    [c.name, c.signed, Object.entries(c.registrations).map(([k, v]) => k + ": " + v).join(", "),
+     c.whitelisted, c.whitelisted_signed].forEach(v => cell(row, v));
+  }
+}
+async function load(p) {
+  if (p < 1) return;
+  const campaign = document.getElementById("campaign").value;
+  const query = new URLSearchParams({ page: p });
+  if (campaign) query.set("campaign", campaign);
+  const result = await (await fetch("api/signed?" + query)).json();
+  page = result.page;
+  const table = document.getElementById("signed");
+  table.innerHTML = "<tr><th>User</th><th>Campaign</th><th>Kusama</th><th>Moonbeam</th><th>Roles</th><th>Date</th></tr>";
+  for (const s of result.items) {
+    const row = table.insertRow();
+    [s.user_tag, s.campaign, s.ksm_address, s.glmr_address, s.roles, s.create_date].forEach(v => cell(row, v));
+  }
+  document.getElementById("position").textContent = "page " + page + ", " + result.total + " total";
+}
+stats();
+load(1);
+</script>
+</body>
+</html>
+"#;
+
+#[test]
+fn test_dashboard_auth() {
+    let request = |value: &str| {
+        Request::builder()
+            .header(header::AUTHORIZATION, value)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    assert!(is_authorized("secret", &request("Bearer secret")));
+    assert!(!is_authorized("secret", &request("Bearer wrong")));
+    assert!(!is_authorized("secret", &request("Bearer secre")));
+    assert!(!is_authorized("secret", &request("Bearer secrets")));
+    // organiser:secret
+    assert!(is_authorized("secret", &request("Basic b3JnYW5pc2VyOnNlY3JldA==")));
+    assert!(!is_authorized("secret", &Request::new(Body::empty())));
+}
+
+#[test]
+fn test_dashboard_pagination() {
+    let query = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>()
+    };
+
+    let page = |number, size, offset| Ok(Page { number, size, offset });
+    assert_eq!(pagination(&query(&[])), page(1, DEFAULT_PAGE_SIZE, 0));
+    assert_eq!(pagination(&query(&[("page", "3"), ("per_page", "10")])), page(3, 10, 20));
+    assert_eq!(pagination(&query(&[("page", "2"), ("per_page", "5000")])), page(2, MAX_PAGE_SIZE, MAX_PAGE_SIZE));
+    assert!(pagination(&query(&[("page", "0")])).is_err());
+    assert!(pagination(&query(&[("page", "-4")])).is_err());
+    assert!(pagination(&query(&[("per_page", "0")])).is_err());
+    assert!(pagination(&query(&[("page", "9223372036854775807")])).is_err());
+}
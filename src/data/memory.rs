@@ -0,0 +1,416 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::Stderr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use super::{Campaign, RoleAction, RoleJob, SignedProof, StoredSignature, WalletStore};
+use crate::chain::Rule;
+#[cfg(feature = "dashboard")]
+use super::{CampaignStats, RegistrationRow, SignedRow, WhitelistStatus};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedRecord {
+    pub user_id: String,
+    pub user_tag: String,
+    pub campaign: String,
+    pub ksm_address: String,
+    pub glmr_address: String,
+    pub roles: String,
+    pub avatar: String,
+    pub proof: Option<SignedProof>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistrationRecord {
+    pub user_id: String,
+    pub user_tag: String,
+    pub campaign: String,
+    pub address_type: String,
+    pub address: String,
+    pub roles: String,
+    pub avatar: String,
+}
+
+#[derive(Default)]
+struct State {
+    campaigns: BTreeMap<String, Campaign>,
+    whitelist: HashSet<(String, String)>,
+    rules: HashMap<String, Vec<Rule>>,
+    // keyed like the primary keys of the SQL tables
+    signed: BTreeMap<(String, String), SignedRecord>,
+    registrations: BTreeMap<(String, String, String), RegistrationRecord>,
+    role_jobs: BTreeMap<i64, QueuedJob>,
+    next_job_id: i64,
+}
+
+#[derive(Clone)]
+struct QueuedJob {
+    job: RoleJob,
+    status: &'static str,
+    next_attempt: Instant,
+}
+
+/// Keeps everything in memory, for tests and local experiments. Campaign windows are not
+/// tracked, a campaign is open as long as its `open` flag is set.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<State>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_campaign(&self, campaign: Campaign) {
+        let mut state = self.state.lock().unwrap();
+        state.campaigns.insert(campaign.name.clone(), campaign);
+    }
+
+    pub fn add_to_whitelist(&self, campaign: &str, address: &str) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .whitelist
+            .insert((campaign.to_string(), address.to_string()));
+    }
+
+    pub fn add_rule(&self, campaign: &str, rule: Rule) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .rules
+            .entry(campaign.to_string())
+            .or_default()
+            .push(rule);
+    }
+
+    pub fn signed(&self) -> Vec<SignedRecord> {
+        self.state.lock().unwrap().signed.values().cloned().collect()
+    }
+
+    pub fn registrations(&self) -> Vec<RegistrationRecord> {
+        let state = self.state.lock().unwrap();
+        state.registrations.values().cloned().collect()
+    }
+
+    /// Jobs still waiting to be applied, whether due or not.
+    pub fn pending_role_jobs(&self) -> Vec<RoleJob> {
+        self.role_jobs_with_status("pending")
+    }
+
+    fn role_jobs_with_status(&self, status: &str) -> Vec<RoleJob> {
+        let state = self.state.lock().unwrap();
+        state
+            .role_jobs
+            .values()
+            .filter(|queued| queued.status == status)
+            .map(|queued| queued.job.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl WalletStore for MemoryStore {
+    async fn campaign(&self, name: String) -> Result<Campaign, String> {
+        let state = self.state.lock().unwrap();
+        match state.campaigns.get(&name) {
+            Some(campaign) => Ok(campaign.clone()),
+            None => Err(format!("Unknown campaign {}", name)),
+        }
+    }
+
+    async fn campaign_names(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state
+            .campaigns
+            .values()
+            .filter(|c| c.open)
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    async fn campaign_rules(&self, campaign: &str) -> Result<Vec<Rule>, String> {
+        let state = self.state.lock().unwrap();
+        Ok(state.rules.get(campaign).cloned().unwrap_or_default())
+    }
+
+    async fn whitelisted(&self, campaign: &str, address: &str) -> Result<bool, String> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .whitelist
+            .contains(&(campaign.to_string(), address.to_string())))
+    }
+
+    async fn insert_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        ksm_address: String,
+        glmr_address: String,
+        roles: String,
+        avatar: String,
+        proof: SignedProof,
+    ) -> Option<Stderr> {
+        let mut state = self.state.lock().unwrap();
+        state.signed.insert(
+            (user_id.clone(), campaign.clone()),
+            SignedRecord {
+                user_id,
+                user_tag,
+                campaign,
+                ksm_address,
+                glmr_address,
+                roles,
+                avatar,
+                proof: Some(proof),
+            },
+        );
+        None
+    }
+
+    async fn signed_proofs(&self) -> Result<Vec<StoredSignature>, String> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .signed
+            .values()
+            .map(|s| StoredSignature {
+                user_id: s.user_id.clone(),
+                campaign: s.campaign.clone(),
+                ksm_address: s.ksm_address.clone(),
+                glmr_address: s.glmr_address.clone(),
+                proof: s.proof.clone(),
+            })
+            .collect())
+    }
+
+    async fn signed_users(&self, ksm_address: &str) -> Result<Vec<String>, String> {
+        let state = self.state.lock().unwrap();
+        let mut users: Vec<String> = state
+            .signed
+            .values()
+            .filter(|s| s.ksm_address == ksm_address)
+            .map(|s| s.user_id.clone())
+            .collect();
+        users.dedup();
+        Ok(users)
+    }
+
+    async fn insert_non_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        address_type: String,
+        address: String,
+        roles: String,
+        avatar: String,
+        grant_role: Option<u64>,
+    ) -> Option<Stderr> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(role_id) = grant_role {
+            state.next_job_id += 1;
+            let id = state.next_job_id;
+            state.role_jobs.insert(
+                id,
+                QueuedJob {
+                    job: RoleJob {
+                        id,
+                        user_id: user_id.clone(),
+                        role_id,
+                        action: RoleAction::Grant,
+                        attempts: 0,
+                        last_error: None,
+                    },
+                    status: "pending",
+                    next_attempt: Instant::now(),
+                },
+            );
+        }
+        state.registrations.insert(
+            (user_id.clone(), campaign.clone(), address_type.clone()),
+            RegistrationRecord {
+                user_id,
+                user_tag,
+                campaign,
+                address_type,
+                address,
+                roles,
+                avatar,
+            },
+        );
+        None
+    }
+
+    async fn claim_role_jobs(&self, limit: i64, lease: Duration) -> Result<Vec<RoleJob>, String> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        Ok(state
+            .role_jobs
+            .values_mut()
+            .filter(|queued| queued.status == "pending" && queued.next_attempt <= now)
+            .take(limit as usize)
+            .map(|queued| {
+                queued.next_attempt = now + lease;
+                queued.job.clone()
+            })
+            .collect())
+    }
+
+    async fn complete_role_job(&self, id: i64) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(queued) = state.role_jobs.get_mut(&id) {
+            queued.status = "done";
+            queued.job.attempts += 1;
+        }
+        Ok(())
+    }
+
+    async fn fail_role_job(&self, id: i64, error: String, retry_in: Option<Duration>) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(queued) = state.role_jobs.get_mut(&id) {
+            queued.status = if retry_in.is_some() { "pending" } else { "failed" };
+            queued.next_attempt = Instant::now() + retry_in.unwrap_or_default();
+            queued.job.attempts += 1;
+            queued.job.last_error = Some(error);
+        }
+        Ok(())
+    }
+
+    async fn failed_role_jobs(&self) -> Result<Vec<RoleJob>, String> {
+        Ok(self.role_jobs_with_status("failed"))
+    }
+
+    async fn retry_failed_role_jobs(&self) -> Result<u64, String> {
+        let mut state = self.state.lock().unwrap();
+        let mut count = 0;
+        for queued in state.role_jobs.values_mut().filter(|queued| queued.status == "failed") {
+            queued.status = "pending";
+            queued.next_attempt = Instant::now();
+            queued.job.attempts = 0;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn close(&self) {}
+
+    async fn row_counts(&self) -> Result<Vec<(&'static str, i64)>, String> {
+        let state = self.state.lock().unwrap();
+        Ok(vec![
+            ("SIGNED", state.signed.len() as i64),
+            ("users", state.registrations.len() as i64),
+            ("CAMPAIGN_WHITELIST", state.whitelist.len() as i64),
+        ])
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn signed_page(
+        &self,
+        campaign: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<SignedRow>, i64), String> {
+        let state = self.state.lock().unwrap();
+        let rows = state
+            .signed
+            .values()
+            .filter(|s| campaign.is_none() || campaign.as_ref() == Some(&s.campaign))
+            .collect::<Vec<_>>();
+        let total = rows.len() as i64;
+        let page = rows
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|s| SignedRow {
+                user_id: s.user_id.clone(),
+                user_tag: s.user_tag.clone(),
+                campaign: s.campaign.clone(),
+                ksm_address: s.ksm_address.clone(),
+                glmr_address: s.glmr_address.clone(),
+                roles: Some(s.roles.clone()),
+                create_date: String::new(),
+            })
+            .collect();
+        Ok((page, total))
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn registration_page(
+        &self,
+        campaign: Option<String>,
+        chain: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RegistrationRow>, i64), String> {
+        let state = self.state.lock().unwrap();
+        let rows = state
+            .registrations
+            .values()
+            .filter(|r| campaign.is_none() || campaign.as_ref() == Some(&r.campaign))
+            .filter(|r| chain.is_none() || chain.as_ref() == Some(&r.address_type))
+            .collect::<Vec<_>>();
+        let total = rows.len() as i64;
+        let page = rows
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|r| RegistrationRow {
+                user_id: r.user_id.clone(),
+                user_tag: r.user_tag.clone(),
+                campaign: r.campaign.clone(),
+                address_type: r.address_type.clone(),
+                address: r.address.clone(),
+                roles: Some(r.roles.clone()),
+                update_date: String::new(),
+            })
+            .collect();
+        Ok((page, total))
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, String> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .campaigns
+            .keys()
+            .map(|name| {
+                let signed = state.signed.values().filter(|s| &s.campaign == name);
+                let mut registrations = BTreeMap::new();
+                for r in state.registrations.values().filter(|r| &r.campaign == name) {
+                    *registrations.entry(r.address_type.clone()).or_insert(0) += 1;
+                }
+                CampaignStats {
+                    name: name.clone(),
+                    signed: signed.clone().count() as i64,
+                    registrations,
+                    whitelisted: state.whitelist.iter().filter(|(c, _)| c == name).count() as i64,
+                    whitelisted_signed: signed
+                        .filter(|s| state.whitelist.contains(&(name.clone(), s.ksm_address.clone())))
+                        .count() as i64,
+                }
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn whitelist_status(&self, campaign: String, address: String) -> Result<WhitelistStatus, String> {
+        let state = self.state.lock().unwrap();
+        Ok(WhitelistStatus {
+            whitelisted: state.whitelist.contains(&(campaign.clone(), address.clone())),
+            signed: state
+                .signed
+                .values()
+                .any(|s| s.campaign == campaign && s.ksm_address == address),
+            campaign,
+            address,
+        })
+    }
+}
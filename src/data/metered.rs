@@ -0,0 +1,153 @@
+use std::{io::Stderr, time::Duration};
+
+use async_trait::async_trait;
+
+use super::{Campaign, RoleJob, SignedProof, StoredSignature, WalletStore};
+use crate::chain::Rule;
+use crate::metrics::SQL_LATENCY;
+#[cfg(feature = "dashboard")]
+use super::{CampaignStats, RegistrationRow, SignedRow, WhitelistStatus};
+
+/// Records the latency of every query of the wrapped store, labelled by method.
+pub struct MeteredStore<S>(pub S);
+
+#[async_trait]
+impl<S: WalletStore> WalletStore for MeteredStore<S> {
+    async fn campaign(&self, name: String) -> Result<Campaign, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["campaign"]).start_timer();
+        self.0.campaign(name).await
+    }
+
+    async fn campaign_names(&self) -> Vec<String> {
+        let _timer = SQL_LATENCY.with_label_values(&["campaign_names"]).start_timer();
+        self.0.campaign_names().await
+    }
+
+    async fn campaign_rules(&self, campaign: &str) -> Result<Vec<Rule>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["campaign_rules"]).start_timer();
+        self.0.campaign_rules(campaign).await
+    }
+
+    async fn whitelisted(&self, campaign: &str, address: &str) -> Result<bool, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["whitelisted"]).start_timer();
+        self.0.whitelisted(campaign, address).await
+    }
+
+    async fn insert_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        ksm_address: String,
+        glmr_address: String,
+        roles: String,
+        avatar: String,
+        proof: SignedProof,
+    ) -> Option<Stderr> {
+        let _timer = SQL_LATENCY.with_label_values(&["insert_signed"]).start_timer();
+        self.0
+            .insert_signed(user_id, user_tag, campaign, ksm_address, glmr_address, roles, avatar, proof)
+            .await
+    }
+
+    async fn signed_proofs(&self) -> Result<Vec<StoredSignature>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["signed_proofs"]).start_timer();
+        self.0.signed_proofs().await
+    }
+
+    async fn signed_users(&self, ksm_address: &str) -> Result<Vec<String>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["signed_users"]).start_timer();
+        self.0.signed_users(ksm_address).await
+    }
+
+    async fn insert_non_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        address_type: String,
+        address: String,
+        roles: String,
+        avatar: String,
+        grant_role: Option<u64>,
+    ) -> Option<Stderr> {
+        let _timer = SQL_LATENCY.with_label_values(&["insert_non_signed"]).start_timer();
+        self.0
+            .insert_non_signed(user_id, user_tag, campaign, address_type, address, roles, avatar, grant_role)
+            .await
+    }
+
+    async fn claim_role_jobs(&self, limit: i64, lease: Duration) -> Result<Vec<RoleJob>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["claim_role_jobs"]).start_timer();
+        self.0.claim_role_jobs(limit, lease).await
+    }
+
+    async fn complete_role_job(&self, id: i64) -> Result<(), String> {
+        let _timer = SQL_LATENCY.with_label_values(&["complete_role_job"]).start_timer();
+        self.0.complete_role_job(id).await
+    }
+
+    async fn fail_role_job(&self, id: i64, error: String, retry_in: Option<Duration>) -> Result<(), String> {
+        let _timer = SQL_LATENCY.with_label_values(&["fail_role_job"]).start_timer();
+        self.0.fail_role_job(id, error, retry_in).await
+    }
+
+    async fn failed_role_jobs(&self) -> Result<Vec<RoleJob>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["failed_role_jobs"]).start_timer();
+        self.0.failed_role_jobs().await
+    }
+
+    async fn retry_failed_role_jobs(&self) -> Result<u64, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["retry_failed_role_jobs"]).start_timer();
+        self.0.retry_failed_role_jobs().await
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        let _timer = SQL_LATENCY.with_label_values(&["ping"]).start_timer();
+        self.0.ping().await
+    }
+
+    async fn close(&self) {
+        self.0.close().await;
+    }
+
+    async fn row_counts(&self) -> Result<Vec<(&'static str, i64)>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["row_counts"]).start_timer();
+        self.0.row_counts().await
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn signed_page(
+        &self,
+        campaign: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<SignedRow>, i64), String> {
+        let _timer = SQL_LATENCY.with_label_values(&["signed_page"]).start_timer();
+        self.0.signed_page(campaign, limit, offset).await
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn registration_page(
+        &self,
+        campaign: Option<String>,
+        chain: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RegistrationRow>, i64), String> {
+        let _timer = SQL_LATENCY.with_label_values(&["registration_page"]).start_timer();
+        self.0.registration_page(campaign, chain, limit, offset).await
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["campaign_stats"]).start_timer();
+        self.0.campaign_stats().await
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn whitelist_status(&self, campaign: String, address: String) -> Result<WhitelistStatus, String> {
+        let _timer = SQL_LATENCY.with_label_values(&["whitelist_status"]).start_timer();
+        self.0.whitelist_status(campaign, address).await
+    }
+}
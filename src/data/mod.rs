@@ -0,0 +1,368 @@
+use std::{io::Stderr, ops::Deref, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::chain::Rule;
+
+mod memory;
+mod metered;
+mod postgres;
+mod sqlite;
+
+pub use memory::{MemoryStore, RegistrationRecord, SignedRecord};
+pub use metered::MeteredStore;
+
+/// A collection drive with its own whitelist, role requirements, accepted chains and window.
+#[derive(Clone)]
+pub struct Campaign {
+    pub name: String,
+    pub whitelist: bool,
+    pub required_roles: Vec<String>,
+    pub chains: Vec<String>,
+    pub open: bool,
+    /// Accepts MetaMask signatures of the Kusama address, which only prove the Moonbeam key.
+    pub evm_signatures: bool,
+}
+
+impl Campaign {
+    pub fn accepts_chain(&self, chain: &str) -> bool {
+        self.chains.iter().any(|c| c.eq_ignore_ascii_case(chain))
+    }
+
+    /// A campaign without required roles is open to everyone, otherwise any one of them is enough.
+    pub fn allows_roles(&self, user_roles: &[String]) -> bool {
+        self.required_roles.is_empty()
+            || user_roles
+                .iter()
+                .any(|role| self.required_roles.contains(role))
+    }
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Rows with an unknown kind or amount are reported as errors rather than skipped,
+/// so a typo never silently opens a campaign.
+fn parse_rule(kind: String, contract: Option<String>, min_amount: String) -> Result<Rule, String> {
+    let min = min_amount
+        .parse::<u128>()
+        .map_err(|_| format!("Invalid minimum amount for {} rule", kind))?;
+    match (kind.as_str(), contract) {
+        ("substrate_balance", _) => Ok(Rule::SubstrateBalance { min }),
+        ("erc721", Some(contract)) => Ok(Rule::Erc721Balance { contract, min }),
+        (kind, _) => Err(format!("Unsupported campaign rule {}", kind)),
+    }
+}
+
+/// What a role job does to the user. The bot never takes a role away, so there are only grants;
+/// the `ACTION` column leaves room for removals once something needs them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoleAction {
+    Grant,
+}
+
+impl RoleAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RoleAction::Grant => "grant",
+        }
+    }
+}
+
+/// A queued role change, applied by the role worker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleJob {
+    pub id: i64,
+    pub user_id: String,
+    pub role_id: u64,
+    pub action: RoleAction,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+}
+
+fn role_job(
+    id: i64,
+    user_id: String,
+    role_id: String,
+    action: String,
+    attempts: i64,
+    last_error: Option<String>,
+) -> Result<RoleJob, String> {
+    let role_id = role_id
+        .parse()
+        .map_err(|_| format!("Invalid role id {} in role job {}", role_id, id))?;
+    let action = match action.as_str() {
+        "grant" => RoleAction::Grant,
+        other => return Err(format!("Unknown action {} in role job {}", other, id)),
+    };
+    Ok(RoleJob {
+        id,
+        user_id,
+        role_id,
+        action,
+        attempts,
+        last_error,
+    })
+}
+
+/// What a SIGNED row was accepted on, kept so the signature can be checked again later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedProof {
+    /// Hex encoded, as submitted.
+    pub signature: String,
+    /// Hex encoded bytes the signature covers.
+    pub message: String,
+    /// The signature scheme that verified it, e.g. `sr25519`.
+    pub scheme: String,
+    /// How the GLMR address was encoded into the message, e.g. `wrapped_bytes`.
+    pub format: String,
+    /// Set when the KSM address is a multisig account signed for by one of its signatories.
+    pub multisig: Option<MultisigProof>,
+}
+
+pub use wallet_verify::MultisigProof;
+
+/// A SIGNED row with its proof. Rows recorded before proofs were kept have none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSignature {
+    pub user_id: String,
+    pub campaign: String,
+    pub ksm_address: String,
+    pub glmr_address: String,
+    pub proof: Option<SignedProof>,
+}
+
+/// The columns of a SIGNED row read back for re-verification.
+#[derive(sqlx::FromRow)]
+struct SignedColumns {
+    user_id: String,
+    campaign: String,
+    ksm_address: String,
+    glmr_address: String,
+    signature: Option<String>,
+    message: Option<String>,
+    scheme: Option<String>,
+    format: Option<String>,
+    signer: Option<String>,
+    signatories: Option<String>,
+    threshold: Option<i64>,
+}
+
+impl From<SignedColumns> for StoredSignature {
+    fn from(row: SignedColumns) -> Self {
+        let multisig = match (row.signer, row.signatories, row.threshold) {
+            (Some(signer), Some(signatories), Some(threshold)) => Some(MultisigProof {
+                signer,
+                signatories: split_list(&signatories),
+                threshold: threshold as u16,
+            }),
+            _ => None,
+        };
+        let proof = match (row.signature, row.message, row.scheme) {
+            (Some(signature), Some(message), Some(scheme)) => Some(SignedProof {
+                signature,
+                message,
+                scheme,
+                // rows stored before other formats were accepted all used the polkadot.js format
+                format: row.format.unwrap_or_else(|| "wrapped_bytes".to_string()),
+                multisig,
+            }),
+            _ => None,
+        };
+        StoredSignature {
+            user_id: row.user_id,
+            campaign: row.campaign,
+            ksm_address: row.ksm_address,
+            glmr_address: row.glmr_address,
+            proof,
+        }
+    }
+}
+
+/// Tables whose size is exported as a metric.
+pub const COUNTED_TABLES: [&str; 3] = ["SIGNED", "users", "CAMPAIGN_WHITELIST"];
+
+/// Storage of campaigns and the wallets collected for them.
+#[async_trait]
+pub trait WalletStore: Send + Sync {
+    async fn campaign(&self, name: String) -> Result<Campaign, String>;
+
+    /// Names of the campaigns that have not ended yet.
+    async fn campaign_names(&self) -> Vec<String>;
+
+    /// On-chain rules of the campaign.
+    async fn campaign_rules(&self, campaign: &str) -> Result<Vec<Rule>, String>;
+
+    async fn whitelisted(&self, campaign: &str, address: &str) -> Result<bool, String>;
+
+    async fn check_whitelist(&self, campaign: &Campaign, address: String) -> Result<(), String> {
+        if !campaign.whitelist {
+            return Ok(());
+        }
+
+        match self.whitelisted(&campaign.name, &address).await {
+            Ok(true) => Ok(()),
+            _ => Err(format!("Address is not on the {} whitelist", campaign.name)),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        ksm_address: String,
+        glmr_address: String,
+        roles: String,
+        avatar: String,
+        proof: SignedProof,
+    ) -> Option<Stderr>;
+
+    /// Every SIGNED row with its stored proof, for re-verification.
+    async fn signed_proofs(&self) -> Result<Vec<StoredSignature>, String>;
+
+    /// The users who signed for the Kusama address, in any campaign.
+    async fn signed_users(&self, ksm_address: &str) -> Result<Vec<String>, String>;
+
+    /// Records a registration. When `grant_role` is set, a job granting it is queued in the
+    /// same transaction, so a recorded wallet never misses its role.
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_non_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        address_type: String,
+        address: String,
+        roles: String,
+        avatar: String,
+        grant_role: Option<u64>,
+    ) -> Option<Stderr>;
+
+    /// Claims the pending role jobs whose next attempt is due, oldest first, by pushing their next
+    /// attempt back by `lease`. Another worker will not pick them up until the lease runs out, which
+    /// only happens when the claiming worker died before recording the outcome.
+    async fn claim_role_jobs(&self, limit: i64, lease: Duration) -> Result<Vec<RoleJob>, String>;
+
+    async fn complete_role_job(&self, id: i64) -> Result<(), String>;
+
+    /// Records a failed attempt, the job is tried again after `retry_in` or marked failed when it is `None`.
+    async fn fail_role_job(&self, id: i64, error: String, retry_in: Option<Duration>) -> Result<(), String>;
+
+    async fn failed_role_jobs(&self) -> Result<Vec<RoleJob>, String>;
+
+    /// Queues the failed jobs again with a fresh attempt count, returning how many.
+    async fn retry_failed_role_jobs(&self) -> Result<u64, String>;
+
+    /// Runs a trivial query, to tell whether the database is reachable.
+    async fn ping(&self) -> Result<(), String>;
+
+    /// Closes the connections once the bot has stopped.
+    async fn close(&self);
+
+    /// Number of rows in each of the [`COUNTED_TABLES`].
+    async fn row_counts(&self) -> Result<Vec<(&'static str, i64)>, String>;
+
+    #[cfg(feature = "dashboard")]
+    async fn signed_page(
+        &self,
+        campaign: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<SignedRow>, i64), String>;
+
+    #[cfg(feature = "dashboard")]
+    async fn registration_page(
+        &self,
+        campaign: Option<String>,
+        chain: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RegistrationRow>, i64), String>;
+
+    /// Per campaign totals: signed users, registrations by chain and whitelist coverage.
+    #[cfg(feature = "dashboard")]
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, String>;
+
+    #[cfg(feature = "dashboard")]
+    async fn whitelist_status(&self, campaign: String, address: String) -> Result<WhitelistStatus, String>;
+}
+
+/// Shared handle to the configured store.
+#[derive(Clone)]
+pub struct DbClient(Arc<dyn WalletStore>);
+
+impl DbClient {
+    pub fn new(store: Arc<dyn WalletStore>) -> Self {
+        DbClient(store)
+    }
+}
+
+impl Deref for DbClient {
+    type Target = dyn WalletStore;
+
+    fn deref(&self) -> &Self::Target {
+        self.0.as_ref()
+    }
+}
+
+#[cfg(feature = "dashboard")]
+#[derive(serde::Serialize, sqlx::FromRow)]
+pub struct SignedRow {
+    pub user_id: String,
+    pub user_tag: String,
+    pub campaign: String,
+    pub ksm_address: String,
+    pub glmr_address: String,
+    pub roles: Option<String>,
+    pub create_date: String,
+}
+
+#[cfg(feature = "dashboard")]
+#[derive(serde::Serialize, sqlx::FromRow)]
+pub struct RegistrationRow {
+    pub user_id: String,
+    pub user_tag: String,
+    pub campaign: String,
+    pub address_type: String,
+    pub address: String,
+    pub roles: Option<String>,
+    pub update_date: String,
+}
+
+#[cfg(feature = "dashboard")]
+#[derive(serde::Serialize)]
+pub struct CampaignStats {
+    pub name: String,
+    pub signed: i64,
+    pub registrations: std::collections::BTreeMap<String, i64>,
+    pub whitelisted: i64,
+    pub whitelisted_signed: i64,
+}
+
+#[cfg(feature = "dashboard")]
+#[derive(serde::Serialize)]
+pub struct WhitelistStatus {
+    pub campaign: String,
+    pub address: String,
+    pub whitelisted: bool,
+    pub signed: bool,
+}
+
+/// Connects to the database behind `url`: `postgres://` URLs use Postgres, anything else is
+/// treated as a SQLite file, with or without the `sqlite:` prefix. Query latencies are recorded
+/// in the metrics.
+pub async fn init(url: String) -> DbClient {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        DbClient(Arc::new(MeteredStore(postgres::init(&url).await)))
+    } else {
+        let filename = url.strip_prefix("sqlite:").unwrap_or(&url);
+        DbClient(Arc::new(MeteredStore(sqlite::init(filename.to_string()).await)))
+    }
+}
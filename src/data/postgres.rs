@@ -0,0 +1,415 @@
+use std::{io::Stderr, time::Duration};
+
+use async_trait::async_trait;
+use tracing::error;
+use sqlx::Row;
+
+use super::{
+    parse_rule, role_job, split_list, Campaign, RoleJob, SignedColumns, SignedProof, StoredSignature,
+    WalletStore, COUNTED_TABLES,
+};
+use crate::chain::Rule;
+#[cfg(feature = "dashboard")]
+use super::{CampaignStats, RegistrationRow, SignedRow, WhitelistStatus};
+
+// The query macros are checked against the SQLite schema at build time, so the
+// Postgres queries are plain runtime queries.
+pub struct PostgresStore {
+    database: sqlx::PgPool,
+}
+
+#[async_trait]
+impl WalletStore for PostgresStore {
+    async fn campaign(&self, name: String) -> Result<Campaign, String> {
+        let campaign = sqlx::query(
+            "select NAME, WHITELIST, REQUIRED_ROLES, CHAINS, EVM_SIGNATURES,
+            (START_DATE IS NULL OR START_DATE <= (now() at time zone 'utc')) AND (END_DATE IS NULL OR END_DATE > (now() at time zone 'utc')) as OPEN
+            from CAMPAIGN where NAME = $1")
+        .bind(&name)
+        .fetch_one(&self.database)
+        .await;
+
+        match campaign {
+            Ok(c) => Ok(Campaign {
+                name: c.get("name"),
+                whitelist: c.get("whitelist"),
+                required_roles: split_list(c.get("required_roles")),
+                chains: split_list(c.get("chains")),
+                open: c.get("open"),
+                evm_signatures: c.get("evm_signatures"),
+            }),
+            Err(_) => Err(format!("Unknown campaign {}", name)),
+        }
+    }
+
+    async fn campaign_names(&self) -> Vec<String> {
+        sqlx::query_scalar("select NAME from CAMPAIGN where END_DATE IS NULL OR END_DATE > (now() at time zone 'utc') order by NAME")
+            .fetch_all(&self.database)
+            .await
+            .unwrap_or_default()
+    }
+
+    async fn campaign_rules(&self, campaign: &str) -> Result<Vec<Rule>, String> {
+        let rows = sqlx::query("select KIND, CONTRACT, MIN_AMOUNT from CAMPAIGN_RULE where CAMPAIGN = $1")
+            .bind(campaign)
+            .fetch_all(&self.database)
+            .await
+            .map_err(|_| "Could not load the campaign rules".to_string())?;
+
+        rows.into_iter()
+            .map(|row| parse_rule(row.get("kind"), row.get("contract"), row.get("min_amount")))
+            .collect()
+    }
+
+    async fn whitelisted(&self, campaign: &str, address: &str) -> Result<bool, String> {
+        let entry = sqlx::query("select 1 from CAMPAIGN_WHITELIST where CAMPAIGN = $1 and ADDRESS = $2")
+            .bind(campaign)
+            .bind(address)
+            .fetch_optional(&self.database)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(entry.is_some())
+    }
+
+    async fn insert_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        ksm_address: String,
+        glmr_address: String,
+        roles: String,
+        avatar: String,
+        proof: SignedProof,
+    ) -> Option<Stderr> {
+        let multisig = proof.multisig.as_ref();
+        let inserted = sqlx::query(
+            "INSERT INTO SIGNED (USER_ID, USER_TAG, CAMPAIGN, KSM_ADDRESS, GLMR_ADDRESS, ROLES, AVATAR, SIGNATURE, MESSAGE, SCHEME, FORMAT,
+            SIGNER, SIGNATORIES, THRESHOLD, CREATE_DATE)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, now() at time zone 'utc')
+            ON CONFLICT (USER_ID, CAMPAIGN) DO UPDATE SET USER_TAG = excluded.USER_TAG, KSM_ADDRESS = excluded.KSM_ADDRESS,
+            GLMR_ADDRESS = excluded.GLMR_ADDRESS, ROLES = excluded.ROLES, AVATAR = excluded.AVATAR, SIGNATURE = excluded.SIGNATURE,
+            MESSAGE = excluded.MESSAGE, SCHEME = excluded.SCHEME, FORMAT = excluded.FORMAT, SIGNER = excluded.SIGNER,
+            SIGNATORIES = excluded.SIGNATORIES, THRESHOLD = excluded.THRESHOLD, CREATE_DATE = excluded.CREATE_DATE")
+        .bind(user_id)
+        .bind(user_tag)
+        .bind(campaign)
+        .bind(ksm_address)
+        .bind(glmr_address)
+        .bind(roles)
+        .bind(avatar)
+        .bind(proof.signature)
+        .bind(proof.message)
+        .bind(proof.scheme)
+        .bind(proof.format)
+        .bind(multisig.map(|m| m.signer.clone()))
+        .bind(multisig.map(|m| m.signatories.join(",")))
+        .bind(multisig.map(|m| m.threshold as i32))
+        .execute(&self.database)
+        .await;
+
+        match inserted {
+            Ok(_) => None,
+            Err(e) => {
+                error!(error = %e, "could not insert the signed wallet");
+                Some(std::io::stderr())
+            }
+        }
+    }
+
+    async fn signed_proofs(&self) -> Result<Vec<StoredSignature>, String> {
+        let rows: Vec<SignedColumns> = sqlx::query_as(
+            "select USER_ID, CAMPAIGN, KSM_ADDRESS, GLMR_ADDRESS, SIGNATURE, MESSAGE, SCHEME, FORMAT, SIGNER, SIGNATORIES,
+            THRESHOLD::bigint as THRESHOLD from SIGNED order by CREATE_DATE")
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(rows.into_iter().map(StoredSignature::from).collect())
+    }
+
+    async fn signed_users(&self, ksm_address: &str) -> Result<Vec<String>, String> {
+        sqlx::query_scalar("select distinct USER_ID from SIGNED where KSM_ADDRESS = $1")
+            .bind(ksm_address)
+            .fetch_all(&self.database)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn insert_non_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        address_type: String,
+        address: String,
+        roles: String,
+        avatar: String,
+        grant_role: Option<u64>,
+    ) -> Option<Stderr> {
+        // the transaction rolls back when dropped on an error
+        let inserted: Result<(), sqlx::Error> = async {
+            let mut tx = self.database.begin().await?;
+            sqlx::query(
+                "INSERT INTO users (USER_ID, USER_TAG, CAMPAIGN, ADDRESS_TYPE, ADDRESS, ROLES, AVATAR, CREATE_DATE, UPDATE_DATE)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, now() at time zone 'utc', now() at time zone 'utc')
+                ON CONFLICT (USER_ID, CAMPAIGN, ADDRESS_TYPE) DO UPDATE SET USER_TAG = excluded.USER_TAG, ADDRESS = excluded.ADDRESS,
+                ROLES = excluded.ROLES, AVATAR = excluded.AVATAR, UPDATE_DATE = excluded.UPDATE_DATE")
+            .bind(&user_id)
+            .bind(user_tag)
+            .bind(campaign)
+            .bind(address_type)
+            .bind(address)
+            .bind(roles)
+            .bind(avatar)
+            .execute(&mut tx)
+            .await?;
+            if let Some(role) = grant_role {
+                sqlx::query(
+                    "INSERT INTO ROLE_JOB (USER_ID, ROLE_ID, ACTION, NEXT_ATTEMPT, CREATE_DATE, UPDATE_DATE)
+                    VALUES ($1, $2, 'grant', now() at time zone 'utc', now() at time zone 'utc', now() at time zone 'utc')")
+                .bind(user_id)
+                .bind(role.to_string())
+                .execute(&mut tx)
+                .await?;
+            }
+            tx.commit().await
+        }
+        .await;
+
+        match inserted {
+            Ok(()) => None,
+            Err(e) => {
+                error!(error = %e, "could not insert the wallet");
+                Some(std::io::stderr())
+            }
+        }
+    }
+
+    async fn claim_role_jobs(&self, limit: i64, lease: Duration) -> Result<Vec<RoleJob>, String> {
+        // skipping the rows another worker has locked, so concurrent claims never overlap
+        let rows = sqlx::query(
+            "update ROLE_JOB set NEXT_ATTEMPT = (now() at time zone 'utc') + make_interval(secs => $1)
+            where ID in (select ID from ROLE_JOB where STATUS = 'pending' and NEXT_ATTEMPT <= (now() at time zone 'utc')
+                order by ID limit $2 for update skip locked)
+            returning ID, USER_ID, ROLE_ID, ACTION, ATTEMPTS, LAST_ERROR")
+        .bind(lease.as_secs_f64())
+        .bind(limit)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut jobs = rows.iter().map(row_to_job).collect::<Result<Vec<_>, _>>()?;
+        jobs.sort_by_key(|job| job.id);
+        Ok(jobs)
+    }
+
+    async fn complete_role_job(&self, id: i64) -> Result<(), String> {
+        sqlx::query(
+            "update ROLE_JOB set STATUS = 'done', ATTEMPTS = ATTEMPTS + 1, UPDATE_DATE = now() at time zone 'utc' where ID = $1")
+        .bind(id)
+        .execute(&self.database)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    async fn fail_role_job(&self, id: i64, error: String, retry_in: Option<Duration>) -> Result<(), String> {
+        let status = if retry_in.is_some() { "pending" } else { "failed" };
+        sqlx::query(
+            "update ROLE_JOB set STATUS = $1, ATTEMPTS = ATTEMPTS + 1, LAST_ERROR = $2,
+            NEXT_ATTEMPT = (now() at time zone 'utc') + make_interval(secs => $3), UPDATE_DATE = now() at time zone 'utc'
+            where ID = $4")
+        .bind(status)
+        .bind(error)
+        .bind(retry_in.unwrap_or_default().as_secs_f64())
+        .bind(id)
+        .execute(&self.database)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    async fn failed_role_jobs(&self) -> Result<Vec<RoleJob>, String> {
+        let rows = sqlx::query(
+            "select ID, USER_ID, ROLE_ID, ACTION, ATTEMPTS, LAST_ERROR from ROLE_JOB where STATUS = 'failed' order by ID")
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        rows.iter().map(row_to_job).collect()
+    }
+
+    async fn retry_failed_role_jobs(&self) -> Result<u64, String> {
+        sqlx::query(
+            "update ROLE_JOB set STATUS = 'pending', ATTEMPTS = 0, NEXT_ATTEMPT = now() at time zone 'utc',
+            UPDATE_DATE = now() at time zone 'utc' where STATUS = 'failed'")
+        .execute(&self.database)
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(|e| e.to_string())
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        sqlx::query("select 1")
+            .execute(&self.database)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn close(&self) {
+        self.database.close().await;
+    }
+
+    async fn row_counts(&self) -> Result<Vec<(&'static str, i64)>, String> {
+        let mut counts = vec![];
+        for table in COUNTED_TABLES {
+            let count: i64 = sqlx::query_scalar(&format!("select count(*) from {}", table))
+                .fetch_one(&self.database)
+                .await
+                .map_err(|e| e.to_string())?;
+            counts.push((table, count));
+        }
+        Ok(counts)
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn signed_page(
+        &self,
+        campaign: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<SignedRow>, i64), String> {
+        let rows = sqlx::query_as(
+            "select USER_ID as user_id, USER_TAG as user_tag, CAMPAIGN as campaign, KSM_ADDRESS as ksm_address,
+            GLMR_ADDRESS as glmr_address, ROLES as roles, CREATE_DATE::text as create_date
+            from SIGNED where $1::text IS NULL OR CAMPAIGN = $1 order by CREATE_DATE desc limit $2 offset $3")
+        .bind(&campaign)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let total = sqlx::query_scalar("select count(*) from SIGNED where $1::text IS NULL OR CAMPAIGN = $1")
+            .bind(&campaign)
+            .fetch_one(&self.database)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok((rows, total))
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn registration_page(
+        &self,
+        campaign: Option<String>,
+        chain: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RegistrationRow>, i64), String> {
+        let rows = sqlx::query_as(
+            "select USER_ID as user_id, USER_TAG as user_tag, CAMPAIGN as campaign, ADDRESS_TYPE as address_type,
+            ADDRESS as address, ROLES as roles, UPDATE_DATE::text as update_date
+            from users where ($1::text IS NULL OR CAMPAIGN = $1) and ($2::text IS NULL OR ADDRESS_TYPE = $2)
+            order by UPDATE_DATE desc limit $3 offset $4")
+        .bind(&campaign)
+        .bind(&chain)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let total = sqlx::query_scalar(
+            "select count(*) from users where ($1::text IS NULL OR CAMPAIGN = $1) and ($2::text IS NULL OR ADDRESS_TYPE = $2)")
+        .bind(&campaign)
+        .bind(&chain)
+        .fetch_one(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok((rows, total))
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, String> {
+        let campaigns = sqlx::query(
+            "select c.NAME as name,
+            (select count(*) from SIGNED s where s.CAMPAIGN = c.NAME) as signed,
+            (select count(*) from CAMPAIGN_WHITELIST w where w.CAMPAIGN = c.NAME) as whitelisted,
+            (select count(*) from CAMPAIGN_WHITELIST w join SIGNED s on s.CAMPAIGN = w.CAMPAIGN and s.KSM_ADDRESS = w.ADDRESS
+                where w.CAMPAIGN = c.NAME) as whitelisted_signed
+            from CAMPAIGN c order by c.NAME")
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let chains: Vec<(String, String, i64)> = sqlx::query_as(
+            "select CAMPAIGN, ADDRESS_TYPE, count(*) from users group by CAMPAIGN, ADDRESS_TYPE")
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(campaigns
+            .into_iter()
+            .map(|c| {
+                let name: String = c.get("name");
+                CampaignStats {
+                    registrations: chains
+                        .iter()
+                        .filter(|(campaign, _, _)| *campaign == name)
+                        .map(|(_, address_type, count)| (address_type.clone(), *count))
+                        .collect(),
+                    name,
+                    signed: c.get("signed"),
+                    whitelisted: c.get("whitelisted"),
+                    whitelisted_signed: c.get("whitelisted_signed"),
+                }
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn whitelist_status(&self, campaign: String, address: String) -> Result<WhitelistStatus, String> {
+        let (whitelisted, signed): (bool, bool) = sqlx::query_as(
+            "select
+            exists(select 1 from CAMPAIGN_WHITELIST where CAMPAIGN = $1 and ADDRESS = $2),
+            exists(select 1 from SIGNED where CAMPAIGN = $1 and KSM_ADDRESS = $2)")
+        .bind(&campaign)
+        .bind(&address)
+        .fetch_one(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(WhitelistStatus {
+            campaign,
+            address,
+            whitelisted,
+            signed,
+        })
+    }
+}
+
+fn row_to_job(row: &sqlx::postgres::PgRow) -> Result<RoleJob, String> {
+    role_job(
+        row.get("id"),
+        row.get("user_id"),
+        row.get("role_id"),
+        row.get("action"),
+        row.get::<i32, _>("attempts").into(),
+        row.get("last_error"),
+    )
+}
+
+pub async fn init(url: &str) -> PostgresStore {
+    let database = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(5)
+        .connect(url)
+        .await
+        .expect("Couldn't connect to database");
+
+    PostgresStore { database }
+}
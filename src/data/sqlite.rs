@@ -0,0 +1,386 @@
+use std::{io::Stderr, time::Duration};
+
+use async_trait::async_trait;
+use tracing::error;
+
+use super::{
+    parse_rule, role_job, split_list, Campaign, RoleJob, SignedColumns, SignedProof, StoredSignature,
+    WalletStore, COUNTED_TABLES,
+};
+use crate::chain::Rule;
+#[cfg(feature = "dashboard")]
+use super::{CampaignStats, RegistrationRow, SignedRow, WhitelistStatus};
+
+pub struct SqliteStore {
+    database: sqlx::SqlitePool,
+}
+
+#[async_trait]
+impl WalletStore for SqliteStore {
+    async fn campaign(&self, name: String) -> Result<Campaign, String> {
+        let campaign = sqlx::query!(
+            r#"select NAME as name, WHITELIST as "whitelist: bool", REQUIRED_ROLES as required_roles, CHAINS as chains, EVM_SIGNATURES as "evm_signatures: bool",
+            (START_DATE IS NULL OR START_DATE <= datetime('now')) AND (END_DATE IS NULL OR END_DATE > datetime('now')) as "open!: bool"
+            from CAMPAIGN where NAME = ?"#,
+            name)
+        .fetch_one(&self.database)
+        .await;
+
+        match campaign {
+            Ok(c) => Ok(Campaign {
+                name: c.name,
+                whitelist: c.whitelist,
+                required_roles: split_list(&c.required_roles),
+                chains: split_list(&c.chains),
+                open: c.open,
+                evm_signatures: c.evm_signatures,
+            }),
+            Err(_) => Err(format!("Unknown campaign {}", name)),
+        }
+    }
+
+    async fn campaign_names(&self) -> Vec<String> {
+        sqlx::query!("select NAME as name from CAMPAIGN where END_DATE IS NULL OR END_DATE > datetime('now') order by NAME")
+            .fetch_all(&self.database)
+            .await
+            .map(|rows| rows.into_iter().map(|r| r.name).collect())
+            .unwrap_or_default()
+    }
+
+    async fn campaign_rules(&self, campaign: &str) -> Result<Vec<Rule>, String> {
+        let rows = sqlx::query!(
+            "select KIND as kind, CONTRACT as contract, MIN_AMOUNT as min_amount from CAMPAIGN_RULE where CAMPAIGN = ?",
+            campaign)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|_| "Could not load the campaign rules".to_string())?;
+
+        rows.into_iter()
+            .map(|row| parse_rule(row.kind, row.contract, row.min_amount))
+            .collect()
+    }
+
+    async fn whitelisted(&self, campaign: &str, address: &str) -> Result<bool, String> {
+        let entry = sqlx::query!(
+            "select * from CAMPAIGN_WHITELIST where campaign = ? and address = ?",
+            campaign,
+            address)
+        .fetch_optional(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(entry.is_some())
+    }
+
+    async fn insert_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        ksm_address: String,
+        glmr_address: String,
+        roles: String,
+        avatar: String,
+        proof: SignedProof,
+    ) -> Option<Stderr> {
+        let multisig = proof.multisig.as_ref();
+        let signer = multisig.map(|m| m.signer.clone());
+        let signatories = multisig.map(|m| m.signatories.join(","));
+        let threshold = multisig.map(|m| m.threshold);
+        let inserted = sqlx::query!(
+            "INSERT OR REPLACE INTO signed (user_id, user_tag, campaign, ksm_address, glmr_address, roles, avatar, signature, message, scheme, format, signer, signatories, threshold, create_date) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))",
+             user_id, user_tag, campaign, ksm_address, glmr_address, roles, avatar, proof.signature, proof.message, proof.scheme, proof.format, signer, signatories, threshold)
+        .execute(&self.database)
+        .await;
+
+        match inserted {
+            Ok(_) => None,
+            Err(e) => {
+                error!(error = %e, "could not insert the signed wallet");
+                Some(std::io::stderr())
+            }
+        }
+    }
+
+    async fn signed_proofs(&self) -> Result<Vec<StoredSignature>, String> {
+        let rows = sqlx::query_as!(
+            SignedColumns,
+            "select USER_ID as user_id, CAMPAIGN as campaign, KSM_ADDRESS as ksm_address, GLMR_ADDRESS as glmr_address,
+            SIGNATURE as signature, MESSAGE as message, SCHEME as scheme, FORMAT as format, SIGNER as signer,
+            SIGNATORIES as signatories, THRESHOLD as threshold from SIGNED order by CREATE_DATE")
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(rows.into_iter().map(StoredSignature::from).collect())
+    }
+
+    async fn signed_users(&self, ksm_address: &str) -> Result<Vec<String>, String> {
+        sqlx::query!("select distinct USER_ID as user_id from SIGNED where KSM_ADDRESS = ?", ksm_address)
+            .fetch_all(&self.database)
+            .await
+            .map(|rows| rows.into_iter().map(|r| r.user_id).collect())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn insert_non_signed(
+        &self,
+        user_id: String,
+        user_tag: String,
+        campaign: String,
+        address_type: String,
+        address: String,
+        roles: String,
+        avatar: String,
+        grant_role: Option<u64>,
+    ) -> Option<Stderr> {
+        // the transaction rolls back when dropped on an error
+        let inserted: Result<(), sqlx::Error> = async {
+            let mut tx = self.database.begin().await?;
+            sqlx::query!(
+                "INSERT OR REPLACE INTO users (user_id, user_tag, campaign, address_type, address, roles, avatar, create_date, update_date) VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))",
+                 user_id, user_tag, campaign, address_type, address, roles, avatar)
+            .execute(&mut tx)
+            .await?;
+            if let Some(role) = grant_role.map(|role| role.to_string()) {
+                sqlx::query!(
+                    "INSERT INTO ROLE_JOB (USER_ID, ROLE_ID, ACTION, NEXT_ATTEMPT, CREATE_DATE, UPDATE_DATE) VALUES (?, ?, 'grant', datetime('now'), datetime('now'), datetime('now'))",
+                    user_id, role)
+                .execute(&mut tx)
+                .await?;
+            }
+            tx.commit().await
+        }
+        .await;
+
+        match inserted {
+            Ok(()) => None,
+            Err(e) => {
+                error!(error = %e, "could not insert the wallet");
+                Some(std::io::stderr())
+            }
+        }
+    }
+
+    async fn claim_role_jobs(&self, limit: i64, lease: Duration) -> Result<Vec<RoleJob>, String> {
+        // a single statement, SQLite runs it under its write lock
+        let lease = format!("+{} seconds", lease.as_secs());
+        let rows = sqlx::query!(
+            r#"update ROLE_JOB set NEXT_ATTEMPT = datetime('now', ?)
+            where ID in (select ID from ROLE_JOB where STATUS = 'pending' and NEXT_ATTEMPT <= datetime('now') order by ID limit ?)
+            returning ID as "id!: i64", USER_ID as user_id, ROLE_ID as role_id, ACTION as action, ATTEMPTS as "attempts: i64", LAST_ERROR as last_error"#,
+            lease, limit)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut jobs = rows
+            .into_iter()
+            .map(|r| role_job(r.id, r.user_id, r.role_id, r.action, r.attempts, r.last_error))
+            .collect::<Result<Vec<_>, _>>()?;
+        jobs.sort_by_key(|job| job.id);
+        Ok(jobs)
+    }
+
+    async fn complete_role_job(&self, id: i64) -> Result<(), String> {
+        sqlx::query!(
+            "update ROLE_JOB set STATUS = 'done', ATTEMPTS = ATTEMPTS + 1, UPDATE_DATE = datetime('now') where ID = ?",
+            id)
+        .execute(&self.database)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    async fn fail_role_job(&self, id: i64, error: String, retry_in: Option<Duration>) -> Result<(), String> {
+        let status = if retry_in.is_some() { "pending" } else { "failed" };
+        let delay = format!("+{} seconds", retry_in.unwrap_or_default().as_secs());
+        sqlx::query!(
+            "update ROLE_JOB set STATUS = ?, ATTEMPTS = ATTEMPTS + 1, LAST_ERROR = ?, NEXT_ATTEMPT = datetime('now', ?), UPDATE_DATE = datetime('now') where ID = ?",
+            status, error, delay, id)
+        .execute(&self.database)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    async fn failed_role_jobs(&self) -> Result<Vec<RoleJob>, String> {
+        let rows = sqlx::query!(
+            r#"select ID as "id!: i64", USER_ID as user_id, ROLE_ID as role_id, ACTION as action, ATTEMPTS as "attempts: i64", LAST_ERROR as last_error
+            from ROLE_JOB where STATUS = 'failed' order by ID"#)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        rows.into_iter()
+            .map(|r| role_job(r.id, r.user_id, r.role_id, r.action, r.attempts, r.last_error))
+            .collect()
+    }
+
+    async fn retry_failed_role_jobs(&self) -> Result<u64, String> {
+        sqlx::query!(
+            "update ROLE_JOB set STATUS = 'pending', ATTEMPTS = 0, NEXT_ATTEMPT = datetime('now'), UPDATE_DATE = datetime('now') where STATUS = 'failed'")
+        .execute(&self.database)
+        .await
+        .map(|result| result.rows_affected())
+        .map_err(|e| e.to_string())
+    }
+
+    async fn ping(&self) -> Result<(), String> {
+        sqlx::query("select 1")
+            .execute(&self.database)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn close(&self) {
+        self.database.close().await;
+    }
+
+    async fn row_counts(&self) -> Result<Vec<(&'static str, i64)>, String> {
+        let mut counts = vec![];
+        for table in COUNTED_TABLES {
+            let count: i64 = sqlx::query_scalar(&format!("select count(*) from {}", table))
+                .fetch_one(&self.database)
+                .await
+                .map_err(|e| e.to_string())?;
+            counts.push((table, count));
+        }
+        Ok(counts)
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn signed_page(
+        &self,
+        campaign: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<SignedRow>, i64), String> {
+        let rows = sqlx::query_as!(
+            SignedRow,
+            r#"select USER_ID as user_id, USER_TAG as user_tag, CAMPAIGN as campaign, KSM_ADDRESS as ksm_address,
+            GLMR_ADDRESS as glmr_address, ROLES as roles, CREATE_DATE as "create_date: String"
+            from SIGNED where ? IS NULL OR CAMPAIGN = ? order by CREATE_DATE desc limit ? offset ?"#,
+            campaign, campaign, limit, offset)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let total = sqlx::query!(
+            r#"select count(*) as "total!: i64" from SIGNED where ? IS NULL OR CAMPAIGN = ?"#,
+            campaign, campaign)
+        .fetch_one(&self.database)
+        .await
+        .map_err(|e| e.to_string())?
+        .total;
+
+        Ok((rows, total))
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn registration_page(
+        &self,
+        campaign: Option<String>,
+        chain: Option<String>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RegistrationRow>, i64), String> {
+        let rows = sqlx::query_as!(
+            RegistrationRow,
+            r#"select USER_ID as user_id, USER_TAG as user_tag, CAMPAIGN as campaign, ADDRESS_TYPE as address_type,
+            ADDRESS as address, ROLES as roles, UPDATE_DATE as "update_date: String"
+            from users where (? IS NULL OR CAMPAIGN = ?) and (? IS NULL OR ADDRESS_TYPE = ?)
+            order by UPDATE_DATE desc limit ? offset ?"#,
+            campaign, campaign, chain, chain, limit, offset)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let total = sqlx::query!(
+            r#"select count(*) as "total!: i64" from users where (? IS NULL OR CAMPAIGN = ?) and (? IS NULL OR ADDRESS_TYPE = ?)"#,
+            campaign, campaign, chain, chain)
+        .fetch_one(&self.database)
+        .await
+        .map_err(|e| e.to_string())?
+        .total;
+
+        Ok((rows, total))
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn campaign_stats(&self) -> Result<Vec<CampaignStats>, String> {
+        let campaigns = sqlx::query!(
+            r#"select c.NAME as name,
+            (select count(*) from SIGNED s where s.CAMPAIGN = c.NAME) as "signed!: i64",
+            (select count(*) from CAMPAIGN_WHITELIST w where w.CAMPAIGN = c.NAME) as "whitelisted!: i64",
+            (select count(*) from CAMPAIGN_WHITELIST w join SIGNED s on s.CAMPAIGN = w.CAMPAIGN and s.KSM_ADDRESS = w.ADDRESS
+                where w.CAMPAIGN = c.NAME) as "whitelisted_signed!: i64"
+            from CAMPAIGN c order by c.NAME"#)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let chains = sqlx::query!(
+            r#"select CAMPAIGN as campaign, ADDRESS_TYPE as address_type, count(*) as "count!: i64"
+            from users group by CAMPAIGN, ADDRESS_TYPE"#)
+        .fetch_all(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(campaigns
+            .into_iter()
+            .map(|c| CampaignStats {
+                registrations: chains
+                    .iter()
+                    .filter(|r| r.campaign == c.name)
+                    .map(|r| (r.address_type.clone(), r.count))
+                    .collect(),
+                name: c.name,
+                signed: c.signed,
+                whitelisted: c.whitelisted,
+                whitelisted_signed: c.whitelisted_signed,
+            })
+            .collect())
+    }
+
+    #[cfg(feature = "dashboard")]
+    async fn whitelist_status(&self, campaign: String, address: String) -> Result<WhitelistStatus, String> {
+        let status = sqlx::query!(
+            r#"select
+            exists(select 1 from CAMPAIGN_WHITELIST where CAMPAIGN = ? and ADDRESS = ?) as "whitelisted!: bool",
+            exists(select 1 from SIGNED where CAMPAIGN = ? and KSM_ADDRESS = ?) as "signed!: bool""#,
+            campaign, address, campaign, address)
+        .fetch_one(&self.database)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        Ok(WhitelistStatus {
+            campaign,
+            address,
+            whitelisted: status.whitelisted,
+            signed: status.signed,
+        })
+    }
+}
+
+pub async fn init(filename: String) -> SqliteStore {
+    // Initiate a connection to the database file, creating the file if required.
+    let database = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(
+            sqlx::sqlite::SqliteConnectOptions::new()
+                .filename(filename)
+                .create_if_missing(true),
+        )
+        .await
+        .expect("Couldn't connect to database");
+
+    // Run migrations, which updates the database's schema to the latest version.
+  //  sqlx::migrate!("./migrations/sqlite")
+    //    .run(&database)
+    //    .await
+    //    .expect("Couldn't run database migrations");
+
+    SqliteStore { database }
+}
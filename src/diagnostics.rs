@@ -0,0 +1,148 @@
+use serenity::{
+    model::{
+        guild::Role,
+        id::{ChannelId, GuildId, UserId},
+        Permissions,
+    },
+    prelude::*,
+};
+use tracing::{error, info, warn};
+
+use crate::config::Config;
+use crate::metrics::timed;
+
+/// Permissions the bot needs in the guild: granting the post role.
+pub const REQUIRED_PERMISSIONS: Permissions = Permissions::MANAGE_ROLES;
+
+/// The parts of a guild role the self-check looks at.
+pub struct GuildRole {
+    pub id: u64,
+    pub name: String,
+    pub position: i64,
+    pub permissions: Permissions,
+}
+
+impl From<&Role> for GuildRole {
+    fn from(role: &Role) -> Self {
+        GuildRole {
+            id: role.id.0,
+            name: role.name.clone(),
+            position: role.position,
+            permissions: role.permissions,
+        }
+    }
+}
+
+/// Problems with the role setup: missing pre or post role, the bot's top role not above the
+/// post role or missing permissions. `bot_roles` are the ids of the roles the bot holds.
+pub fn check_roles(config: &Config, roles: &[GuildRole], bot_roles: &[u64]) -> Vec<String> {
+    let mut problems = vec![];
+
+    if !roles.iter().any(|role| role.name == config.pre_role) {
+        problems.push(format!("The pre role {} does not exist", config.pre_role));
+    }
+    let post_role = roles.iter().find(|role| role.id == config.post_role_id);
+    if post_role.is_none() {
+        problems.push(format!("The post role {} does not exist", config.post_role_id));
+    }
+
+    // @everyone shares the guild id and applies to every member
+    let held = roles
+        .iter()
+        .filter(|role| role.id == config.guild_id || bot_roles.contains(&role.id))
+        .collect::<Vec<_>>();
+    let permissions = held
+        .iter()
+        .fold(Permissions::empty(), |granted, role| granted | role.permissions);
+    if !permissions.contains(Permissions::ADMINISTRATOR) && !permissions.contains(REQUIRED_PERMISSIONS) {
+        problems.push(format!(
+            "The bot is missing permissions: {}",
+            REQUIRED_PERMISSIONS - permissions
+        ));
+    }
+
+    if let Some(post_role) = post_role {
+        let top = held.iter().max_by_key(|role| role.position);
+        if top.is_none_or(|top| top.position <= post_role.position) {
+            problems.push(format!(
+                "The bot's highest role {} must be above the post role {}",
+                top.map_or("@everyone", |top| top.name.as_str()),
+                post_role.name
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Checks the role setup of the guild and reports problems in the logs and the admin channel.
+pub async fn self_check(ctx: &Context, config: &Config, bot_id: UserId) {
+    let guild_id = GuildId(config.guild_id);
+    let problems = match (
+        timed("guild_roles", guild_id.roles(&ctx.http)).await,
+        timed("guild_member", guild_id.member(ctx, bot_id)).await,
+    ) {
+        (Ok(roles), Ok(member)) => {
+            let roles = roles.values().map(GuildRole::from).collect::<Vec<_>>();
+            let bot_roles = member.roles.iter().map(|role| role.0).collect::<Vec<_>>();
+            check_roles(config, &roles, &bot_roles)
+        }
+        (Err(why), _) | (_, Err(why)) => vec![format!("Cannot load the guild roles: {}", why)],
+    };
+
+    if problems.is_empty() {
+        info!("self-check passed");
+        return;
+    }
+    for problem in &problems {
+        warn!(%problem, "self-check failed");
+    }
+
+    if let Some(channel_id) = config.admin_channel_id {
+        let report = format!("Self-check found problems:\n- {}", problems.join("\n- "));
+        if let Err(why) = timed("send_message", ChannelId(channel_id).say(&ctx.http, report)).await {
+            error!(error = %why, "Cannot post the self-check to the admin channel");
+        }
+    }
+}
+
+#[test]
+fn test_check_roles() {
+    let config = Config {
+        guild_id: 1,
+        pre_role: "Verified".to_string(),
+        post_role_id: 3,
+        ..Config::default()
+    };
+    let role = |id, name: &str, position, permissions| GuildRole {
+        id,
+        name: name.to_string(),
+        position,
+        permissions,
+    };
+    let mut roles = vec![
+        role(1, "@everyone", 0, Permissions::SEND_MESSAGES),
+        role(2, "Verified", 1, Permissions::empty()),
+        role(3, "Member", 2, Permissions::empty()),
+        role(4, "Bot", 3, Permissions::MANAGE_ROLES),
+    ];
+    assert!(check_roles(&config, &roles, &[4]).is_empty());
+
+    roles[3].position = 2;
+    roles[3].permissions = Permissions::empty();
+    assert_eq!(
+        check_roles(&config, &roles, &[4]),
+        vec![
+            "The bot is missing permissions: Manage Roles",
+            "The bot's highest role Bot must be above the post role Member",
+        ]
+    );
+
+    roles.remove(1);
+    roles[2].permissions = Permissions::ADMINISTRATOR;
+    roles[2].position = 5;
+    assert_eq!(
+        check_roles(&config, &roles, &[4]),
+        vec!["The pre role Verified does not exist"]
+    );
+}
@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Serialize;
+
+use crate::data::DbClient;
+
+/// What the bot knows about its own state, updated by the event handler.
+#[derive(Default)]
+pub struct Health {
+    ready: AtomicBool,
+    commands_registered: AtomicBool,
+    connected: AtomicBool,
+}
+
+/// Result of every readiness check, served as the body of `/readyz`.
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub commands_registered: bool,
+    pub gateway_connected: bool,
+    pub database: bool,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.ready && self.commands_registered && self.gateway_connected && self.database
+    }
+}
+
+impl Health {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The gateway sent `ready`.
+    pub fn set_ready(&self) {
+        self.ready.store(true, Ordering::Relaxed);
+        self.connected.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_commands_registered(&self, registered: bool) {
+        self.commands_registered.store(registered, Ordering::Relaxed);
+    }
+
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// Checks the gateway state and runs a trivial query through the store.
+    pub async fn readiness(&self, db_client: &DbClient) -> Readiness {
+        Readiness {
+            ready: self.ready.load(Ordering::Relaxed),
+            commands_registered: self.commands_registered.load(Ordering::Relaxed),
+            gateway_connected: self.connected.load(Ordering::Relaxed),
+            database: db_client.ping().await.is_ok(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_readiness() {
+    use std::sync::Arc;
+
+    let db_client = DbClient::new(Arc::new(crate::data::MemoryStore::new()));
+    let health = Health::new();
+    assert!(!health.readiness(&db_client).await.is_ready());
+
+    health.set_ready();
+    health.set_commands_registered(true);
+    assert!(health.readiness(&db_client).await.is_ready());
+
+    health.set_connected(false);
+    let readiness = health.readiness(&db_client).await;
+    assert!(!readiness.is_ready());
+    assert!(readiness.database);
+}
@@ -0,0 +1,14 @@
+pub mod bot;
+pub mod chain;
+pub mod commands;
+pub mod config;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod data;
+pub mod diagnostics;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod roles;
+pub mod shutdown;
+pub mod wallet;
@@ -0,0 +1,62 @@
+use tracing_subscriber::EnvFilter;
+
+use crate::config::{Config, LogFormat};
+
+/// Installs the global subscriber. `RUST_LOG`, when set, wins over the configured level.
+pub fn init(config: &Config) {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(&config.log_level));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match config.log_format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().with_current_span(true).init(),
+    }
+}
+
+const KEEP_START: usize = 6;
+const KEEP_END: usize = 4;
+// Anything this long made of base58/hex characters is treated as an address or signature.
+const SECRET_MIN_LEN: usize = 24;
+
+/// Shortens an address or signature to its first and last characters, enough to
+/// correlate log lines with a user report without logging the full value.
+pub fn redact(value: &str) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+    if chars.len() <= KEEP_START + KEEP_END {
+        return value.to_string();
+    }
+    format!(
+        "{}…{}",
+        chars[..KEEP_START].iter().collect::<String>(),
+        chars[chars.len() - KEEP_END..].iter().collect::<String>()
+    )
+}
+
+/// Redacts every address or signature looking word of a message, e.g. a user facing error.
+pub fn redact_text(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let token = word.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+            if token.len() >= SECRET_MIN_LEN && token.chars().all(|c| c.is_ascii_alphanumeric()) {
+                format!("{}{}", redact(token), &word[token.len()..])
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn test_redact() {
+    assert_eq!(
+        redact("EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu"),
+        "EYuduc…Biyu"
+    );
+    assert_eq!(redact("0x1234"), "0x1234");
+    assert_eq!(
+        redact_text("Address 0xb794f5ea0ba39494ce839613fffba74279579268 does not hold 5 KSM."),
+        "Address 0xb794…9268 does not hold 5 KSM."
+    );
+}
@@ -1,150 +1,81 @@
-use serenity::{
-    async_trait,
-    model::{
-        gateway::Ready,
-        id::{GuildId},
-        interactions::{
-            application_command::ApplicationCommandOptionType, Interaction, InteractionResponseType,
-        }, 
+use serenity::{http::Http, prelude::*};
+
+use std::time::Duration;
+
+use exosama_wallet_collector::{
+    bot::Handler,
+    chain::{ChainClient, Eligibility, RpcClient},
+    commands::{
+        sync::{self, Scope},
+        Registry,
     },
-    prelude::*,
+    config::Config,
+    data, logging, metrics,
+    roles::{self, DiscordRoles},
+    shutdown, wallet,
 };
+use tracing::{error, info, warn};
+#[cfg(feature = "dashboard")]
+use exosama_wallet_collector::dashboard;
 
-use wallet::data::DbClient;
-pub mod data;
-mod wallet;
-pub struct Handler {
-    db_client: DbClient,
-    pre_role: String,
-    post_role: String
-}
-
-// const ERROR_POSTFIX: &str = ". Follow the guide here <some link>";
-const ERROR_POSTFIX: &str = "";
-#[async_trait]
-impl EventHandler for Handler {
-    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(command) = interaction {
-            let content = match command.data.name.as_str() {
-                "sign" => match wallet::sign(&ctx, &command, &self).await {
-                    Ok(_) => "Your details have been recorded.".to_string(),
-                    Err(e) => format!("{} {}", e, ERROR_POSTFIX),
-                },
-                "wallet" => match wallet::register(&ctx, &command, &self.db_client, &self).await {
-                    Ok(_) => "Your details have been recorded.".to_string(),
-                    Err(e) => e,
-                },
-                _ => "not implemented :(".to_string(),
-            };
-
-            if let Err(why) = command
-                .create_interaction_response(&ctx.http, |response| {
-                    response
-                        .kind(InteractionResponseType::ChannelMessageWithSource)
-                        .interaction_response_data(|message| message.content(content))
-                })
-                .await
-            {
-                println!("Cannot respond to slash command: {}", why);
-            }
-        }
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("verify") {
+        verify_signature(&args[1..]);
+        return;
     }
 
-    async fn ready(&self, ctx: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
-
-        let guild_id = GuildId(
-            dotenv::var("GUILD_ID")
-                .expect("Expected GUILD_ID in environment")
-                .parse()
-                .expect("GUILD_ID must be an integer"),
-        );
-
-        let commands = GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
-            commands
-                .create_application_command(|command| {
-                    command
-                        .name("sign")
-                        .description("Register and verify wallet")
-                        .create_option(|option| {
-                            option
-                                .name("kusama_address")
-                                .description("Kusama wallet address")
-                                .kind(ApplicationCommandOptionType::String)
-                                .required(true)
-                        })
-                        .create_option(|option| {
-                            option
-                                .name("moonbeam_address")
-                                .description("Moonbeam wallet address")
-                                .kind(ApplicationCommandOptionType::String)
-                                .required(true)
-                        })
-                        .create_option(|option| {
-                            option
-                                .name("signature")
-                                .description(
-                                    "Signature of your GLMR address using your KSM account",
-                                )
-                                .kind(ApplicationCommandOptionType::String)
-                                .required(true)
-                        })
-                })
-                .create_application_command(|command| {
-                    command
-                        .name("wallet")
-                        .description("Register user wallet")
-                        .create_option(|option| {
-                            option
-                                .name("type")
-                                .description("Type of wallet")
-                                .kind(ApplicationCommandOptionType::String)
-                                .required(true)
-                                .add_string_choice("Kusama", "Kusama")
-                                .add_string_choice("Moonbeam", "Moonbeam")
-                                .add_string_choice("Moonriver", "Moonriver")
-                        })
-                        .create_option(|option| {
-                            option
-                                .name("address")
-                                .description("The wallet address")
-                                .kind(ApplicationCommandOptionType::String)
-                                .required(true)
-                        })
-                })
-        })
-        .await;
-
-        let roles = guild_id.roles(&ctx.http).await.unwrap();
-        println!(
-            "I now have the following guild slash commands: {:#?}",
-            commands
-        );
+    dotenv::dotenv().ok();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    logging::init(&config);
+    let db_client = data::init(config.database_url.clone()).await;
 
-        println!(
-            "I found the following roles: {:#?}",
-            roles
-        );
+    if std::env::args().any(|arg| arg == "--sync-commands") {
+        plan_commands(&config, &db_client).await;
+        return;
+    }
+    if std::env::args().any(|arg| arg == "--reverify") {
+        reverify(&db_client).await;
+        return;
     }
-}
 
-#[tokio::main]
-async fn main() {
-    dotenv::dotenv().ok();
-    let token = dotenv::var("DISCORD_TOKEN").expect("Expected a token in the environment");
-    let db_file = dotenv::var("DB_FILE").expect("Expected DB File in the environment");
-    let db_client = data::init(db_file).await;
-    // user needs this role before they can use /sign command
-    let pre_role = dotenv::var("PRE_ROLE").expect("Expected pre role in the environment");
-    // user is assigned this role after successfully using the /sign command
-    let post_role = dotenv::var("POST_ROLE_ID").expect("Expected post role in the environment");
+    #[cfg(feature = "dashboard")]
+    if let (Some(addr), Some(token)) = (config.dashboard_addr, config.dashboard_token.clone()) {
+        tokio::spawn(dashboard::serve(addr, db_client.clone(), token));
+    }
 
-    let handler = Handler { db_client, pre_role, post_role };
+    // optional JSON-RPC endpoints used by campaigns with on-chain rules
+    let substrate_rpc = config.substrate_rpc_url.clone();
+    let evm_rpc = config.evm_rpc_url.clone();
+    let chain_client: Option<Box<dyn ChainClient>> =
+        if substrate_rpc.is_some() || evm_rpc.is_some() {
+            Some(Box::new(RpcClient::new(substrate_rpc, evm_rpc)))
+        } else {
+            None
+        };
+    let eligibility = Eligibility::new(chain_client, Duration::from_secs(config.rule_cache_secs));
 
-    let application_id: u64 = dotenv::var("APPLICATION_ID")
-        .expect("Expected an application id in the environment")
-        .parse()
-        .expect("application id is not a valid id");
+    let token = config.discord_token.clone();
+    let application_id = config.application_id;
+    let metrics_addr = config.metrics_addr;
+    let health_addr = config.health_addr;
+    let guild_id = config.guild_id;
+    let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+    let handler = Handler::new(config, db_client.clone(), eligibility);
+    if let Some(addr) = metrics_addr {
+        tokio::spawn(metrics::serve(addr, db_client.clone(), handler.health()));
+    }
+    if let Some(addr) = health_addr {
+        tokio::spawn(metrics::serve_probes(addr, db_client.clone(), handler.health()));
+    }
+    let in_flight = handler.in_flight();
 
     let mut client = Client::builder(token)
         .event_handler(handler)
@@ -152,21 +83,110 @@ async fn main() {
         .await
         .expect("Error creating client");
 
+    let roles = DiscordRoles::new(client.cache_and_http.http.clone(), guild_id);
+    tokio::spawn(roles::run(roles, db_client.clone()));
+
+    // on SIGTERM/SIGINT: refuse new interactions, let the running ones finish, then disconnect
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        shutdown::signal().await;
+        info!(in_flight = in_flight.in_flight(), "shutting down");
+        if !in_flight.drain(shutdown_timeout).await {
+            warn!(in_flight = in_flight.in_flight(), "interactions still running after the shutdown timeout");
+        }
+        shard_manager.lock().await.shutdown_all().await;
+    });
+
     if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+        error!(error = ?why, "Client error");
+    }
+
+    db_client.close().await;
+    info!("stopped");
+}
+
+/// Prints what the bot would change in the registered slash commands, without changing them.
+async fn plan_commands(config: &Config, db_client: &data::DbClient) {
+    let http = Http::new_with_token_application_id(&config.discord_token, config.application_id);
+    let scope = Scope::from_config(config);
+    let definitions = Registry::default().definitions(&db_client.campaign_names().await);
+
+    let registered = match sync::registered(&http, scope).await {
+        Ok(registered) => registered,
+        Err(why) => {
+            eprintln!("Cannot fetch the registered commands: {}", why);
+            std::process::exit(1);
+        }
+    };
+    let changes = sync::plan(&registered, &definitions);
+    if changes.is_empty() {
+        println!("Commands are up to date ({:?})", scope);
+    }
+    for change in changes {
+        println!("{}", change);
     }
 }
 
-impl Handler {
-    fn db_client(&self) -> &DbClient {
-        &self.db_client
+/// Verifies every stored signature again, exiting with an error when any of them fails.
+async fn reverify(db_client: &data::DbClient) {
+    let report = match wallet::reverify(db_client).await {
+        Ok(report) => report,
+        Err(why) => {
+            eprintln!("Cannot load the stored signatures: {}", why);
+            std::process::exit(1);
+        }
+    };
+    for mismatch in &report.mismatches {
+        println!(
+            "{}\t{}\t{}\t{}",
+            mismatch.user_id, mismatch.campaign, mismatch.ksm_address, mismatch.reason
+        );
     }
-    
-    fn is_valid_role(&self, user_role: &str) -> bool {
-        user_role.eq(&self.pre_role)
+    println!(
+        "{} verified, {} without a stored signature, {} mismatches",
+        report.verified,
+        report.unproven,
+        report.mismatches.len()
+    );
+    if !report.mismatches.is_empty() {
+        std::process::exit(1);
     }
+}
 
-    fn post_role(&self) -> &str {
-        &self.post_role
+/// Checks a signature offline and prints every scheme and message format tried, with why each
+/// failed. Exits with an error when none verifies.
+fn verify_signature(args: &[String]) {
+    let (ss58, h160, signature) = match args {
+        [ss58, h160, signature] => (ss58, h160, signature),
+        _ => {
+            eprintln!("Usage: verify <ss58 address> <h160 address> <signature>");
+            std::process::exit(2);
+        }
+    };
+
+    let attempts = match wallet_verify::diagnose_signature(ss58, h160, signature) {
+        Ok(attempts) => attempts,
+        Err(why) => {
+            println!("{}", why);
+            std::process::exit(1);
+        }
+    };
+    for attempt in &attempts {
+        let result = match &attempt.result {
+            Ok(()) => "verified".to_string(),
+            Err(why) => why.to_string(),
+        };
+        println!("{}\t{}\t{}", attempt.scheme.as_str(), attempt.format.as_str(), result);
     }
-}
\ No newline at end of file
+    match attempts.iter().find(|attempt| attempt.result.is_ok()) {
+        Some(attempt) => println!(
+            "Verified with {} over the {} message",
+            attempt.scheme.as_str(),
+            attempt.format.as_str()
+        ),
+        None => {
+            println!("No scheme and message format verifies the signature");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::{convert::Infallible, future::Future, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
+    Encoder, Histogram, HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use tracing::{error, info, warn};
+
+use crate::data::DbClient;
+use crate::health::Health;
+
+lazy_static! {
+    /// Handled slash commands by command and outcome, `ok` or the kind of error.
+    pub static ref COMMANDS: IntCounterVec = register_int_counter_vec!(
+        "wallet_commands_total",
+        "Slash commands handled, by command and outcome",
+        &["command", "outcome"]
+    )
+    .unwrap();
+    pub static ref SIGNATURE_VERIFICATION: Histogram = register_histogram!(
+        "wallet_signature_verification_seconds",
+        "Time spent verifying a signature"
+    )
+    .unwrap();
+    pub static ref SQL_LATENCY: HistogramVec = register_histogram_vec!(
+        "wallet_sql_query_seconds",
+        "Latency of the database queries, by store method",
+        &["query"]
+    )
+    .unwrap();
+    pub static ref DISCORD_LATENCY: HistogramVec = register_histogram_vec!(
+        "wallet_discord_request_seconds",
+        "Latency of the Discord API calls, by request",
+        &["request"]
+    )
+    .unwrap();
+    /// Role job attempts by outcome: `done`, `retry` or `failed`.
+    pub static ref ROLE_JOBS: IntCounterVec = register_int_counter_vec!(
+        "wallet_role_jobs_total",
+        "Role grant attempts, by outcome",
+        &["outcome"]
+    )
+    .unwrap();
+    /// Refreshed on every scrape.
+    pub static ref ROWS: IntGaugeVec = register_int_gauge_vec!(
+        "wallet_rows",
+        "Rows in the collected tables",
+        &["table"]
+    )
+    .unwrap();
+}
+
+/// Awaits a Discord API call, recording its latency.
+pub async fn timed<T>(request: &str, call: impl Future<Output = T>) -> T {
+    let _timer = DISCORD_LATENCY.with_label_values(&[request]).start_timer();
+    call.await
+}
+
+struct State {
+    db_client: DbClient,
+    health: Arc<Health>,
+    /// Whether `/metrics` is served, the probes always are.
+    metrics: bool,
+}
+
+/// Serves `/metrics` in the Prometheus text format, and the `/healthz` and `/readyz` probes,
+/// until the process exits.
+pub async fn serve(addr: SocketAddr, db_client: DbClient, health: Arc<Health>) {
+    listen(addr, State { db_client, health, metrics: true }).await
+}
+
+/// Serves only the `/healthz` and `/readyz` probes, for deployments that do not scrape metrics
+/// or keep them on another address.
+pub async fn serve_probes(addr: SocketAddr, db_client: DbClient, health: Arc<Health>) {
+    listen(addr, State { db_client, health, metrics: false }).await
+}
+
+async fn listen(addr: SocketAddr, state: State) {
+    let endpoint = if state.metrics { "metrics" } else { "probes" };
+    let state = Arc::new(state);
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let state = state.clone();
+                async move { Ok::<_, Infallible>(handle(&state, request).await) }
+            }))
+        }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(why) => {
+            error!(%addr, endpoint, error = %why, "Cannot start endpoint");
+            return;
+        }
+    };
+
+    info!(endpoint, "Listening on http://{}", addr);
+    if let Err(why) = server.serve(make_service).await {
+        error!(endpoint, error = %why, "Endpoint error");
+    }
+}
+
+async fn handle(state: &State, request: Request<Body>) -> Response<Body> {
+    if request.method() != Method::GET {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    match request.uri().path() {
+        "/metrics" if state.metrics => metrics(&state.db_client).await,
+        // the process is up and serving requests
+        "/healthz" => Response::new(Body::from("ok")),
+        "/readyz" => {
+            let readiness = state.health.readiness(&state.db_client).await;
+            let code = if readiness.is_ready() {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+            Response::builder()
+                .status(code)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_string(&readiness).unwrap()))
+                .unwrap()
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    Response::builder().status(code).body(Body::empty()).unwrap()
+}
+
+async fn metrics(db_client: &DbClient) -> Response<Body> {
+    match db_client.row_counts().await {
+        Ok(counts) => {
+            for (table, count) in counts {
+                ROWS.with_label_values(&[table]).set(count);
+            }
+        }
+        // keep exporting the last known counts
+        Err(why) => warn!(error = %why, "Cannot count rows"),
+    }
+
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    if let Err(why) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        error!(error = %why, "Cannot encode metrics");
+        return status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, encoder.format_type())
+        .body(Body::from(buffer))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    let store = Arc::new(crate::data::MemoryStore::new());
+    store.add_to_whitelist("kanaria", "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu");
+    COMMANDS.with_label_values(&["sign", "whitelist"]).inc();
+    let state = State {
+        db_client: DbClient::new(store),
+        health: Arc::new(Health::new()),
+        metrics: true,
+    };
+    let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+    let response = handle(&state, get("/metrics")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    assert!(body.contains(r#"wallet_rows{table="CAMPAIGN_WHITELIST"} 1"#));
+    assert!(body.contains(r#"wallet_commands_total{command="sign",outcome="whitelist"} 1"#));
+
+    assert_eq!(handle(&state, get("/healthz")).await.status(), StatusCode::OK);
+    assert_eq!(handle(&state, get("/readyz")).await.status(), StatusCode::SERVICE_UNAVAILABLE);
+    state.health.set_ready();
+    state.health.set_commands_registered(true);
+    assert_eq!(handle(&state, get("/readyz")).await.status(), StatusCode::OK);
+    assert_eq!(handle(&state, get("/")).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_probes_endpoint() {
+    let state = State {
+        db_client: DbClient::new(Arc::new(crate::data::MemoryStore::new())),
+        health: Arc::new(Health::new()),
+        metrics: false,
+    };
+    let get = |path: &str| Request::get(path).body(Body::empty()).unwrap();
+
+    assert_eq!(handle(&state, get("/metrics")).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(handle(&state, get("/healthz")).await.status(), StatusCode::OK);
+    assert_eq!(handle(&state, get("/readyz")).await.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
@@ -0,0 +1,205 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use serenity::{http::Http, Error as SerenityError};
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::data::{DbClient, RoleAction, RoleJob};
+use crate::metrics::{timed, ROLE_JOBS};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const BATCH_SIZE: i64 = 20;
+/// Attempts before a job is marked failed and left for an admin.
+const MAX_ATTEMPTS: i64 = 8;
+const BASE_DELAY: Duration = Duration::from_secs(10);
+const MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+/// How long a claimed job is left alone before another worker may take it over.
+const LEASE: Duration = Duration::from_secs(5 * 60);
+
+/// Why a role change could not be applied.
+#[derive(Debug)]
+pub enum ApplyError {
+    /// Worth trying again later, e.g. a rate limit or a network error.
+    Retry(String),
+    /// Will keep failing until someone steps in, e.g. missing permissions or an unknown member.
+    Permanent(String),
+}
+
+/// Applies role changes, on Discord outside of tests.
+#[async_trait]
+pub trait RoleApi: Send + Sync {
+    async fn apply(&self, job: &RoleJob) -> Result<(), ApplyError>;
+}
+
+pub struct DiscordRoles {
+    http: Arc<Http>,
+    guild_id: u64,
+}
+
+impl DiscordRoles {
+    pub fn new(http: Arc<Http>, guild_id: u64) -> Self {
+        DiscordRoles { http, guild_id }
+    }
+}
+
+#[async_trait]
+impl RoleApi for DiscordRoles {
+    async fn apply(&self, job: &RoleJob) -> Result<(), ApplyError> {
+        let user_id = job
+            .user_id
+            .parse()
+            .map_err(|_| ApplyError::Permanent(format!("Invalid user id {}", job.user_id)))?;
+        // the http client already waits out Discord's rate limits before sending
+        let result = match job.action {
+            RoleAction::Grant => {
+                timed(
+                    "add_role",
+                    self.http.add_member_role(self.guild_id, user_id, job.role_id),
+                )
+                .await
+            }
+        };
+        result.map_err(classify)
+    }
+}
+
+/// Client errors other than rate limits mean the request itself is wrong, e.g. a missing
+/// permission or a member who left, so retrying will not help.
+fn classify(error: SerenityError) -> ApplyError {
+    if let SerenityError::Http(http) = &error {
+        if let Some(status) = http.status_code() {
+            if status.is_client_error() && status.as_u16() != 429 {
+                return ApplyError::Permanent(error.to_string());
+            }
+        }
+    }
+    ApplyError::Retry(error.to_string())
+}
+
+/// Exponential delay before the next attempt, `None` once the job should be given up.
+fn backoff(attempts: i64) -> Option<Duration> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let delay = BASE_DELAY * 2u32.pow(attempts.clamp(1, 16) as u32 - 1);
+    Some(delay.min(MAX_DELAY))
+}
+
+/// Applies the due jobs one at a time, returning how many were processed.
+pub async fn process_due(api: &dyn RoleApi, db_client: &DbClient) -> Result<usize, String> {
+    let jobs = db_client.claim_role_jobs(BATCH_SIZE, LEASE).await?;
+    for job in &jobs {
+        match api.apply(job).await {
+            Ok(()) => {
+                info!(job = job.id, user_id = %job.user_id, role = job.role_id, action = job.action.as_str(), "applied role");
+                ROLE_JOBS.with_label_values(&["done"]).inc();
+                db_client.complete_role_job(job.id).await?;
+            }
+            Err(ApplyError::Retry(why)) => {
+                let retry_in = backoff(job.attempts + 1);
+                warn!(job = job.id, error = %why, ?retry_in, "could not apply role");
+                let outcome = if retry_in.is_some() { "retry" } else { "failed" };
+                ROLE_JOBS.with_label_values(&[outcome]).inc();
+                db_client.fail_role_job(job.id, why, retry_in).await?;
+            }
+            Err(ApplyError::Permanent(why)) => {
+                error!(job = job.id, error = %why, "could not apply role, giving up");
+                ROLE_JOBS.with_label_values(&["failed"]).inc();
+                db_client.fail_role_job(job.id, why, None).await?;
+            }
+        }
+    }
+    Ok(jobs.len())
+}
+
+/// Polls the queue until the process exits. Interrupted jobs stay pending and are tried again
+/// once their lease runs out, granting a role twice is harmless.
+pub async fn run(api: impl RoleApi, db_client: DbClient) {
+    loop {
+        match process_due(&api, &db_client).await {
+            // more may be waiting
+            Ok(processed) if processed as i64 == BATCH_SIZE => continue,
+            Ok(_) => (),
+            Err(why) => error!(error = %why, "could not process role jobs"),
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[tokio::test]
+async fn test_role_jobs_retry_then_fail() {
+    use std::sync::Mutex;
+
+    use crate::data::{MemoryStore, WalletStore};
+
+    struct Flaky(Mutex<Vec<ApplyError>>);
+
+    #[async_trait]
+    impl RoleApi for Flaky {
+        async fn apply(&self, _job: &RoleJob) -> Result<(), ApplyError> {
+            match self.0.lock().unwrap().pop() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+    }
+
+    let store = Arc::new(MemoryStore::new());
+    let db_client = DbClient::new(store.clone());
+    let record = |user: &str| {
+        let store = store.clone();
+        let user = user.to_string();
+        async move {
+            let blank = String::new;
+            store
+                .insert_non_signed(user, blank(), "kanaria".into(), "Kusama".into(), blank(), blank(), blank(), Some(7))
+                .await
+        }
+    };
+    record("1").await;
+
+    let api = Flaky(Mutex::new(vec![ApplyError::Retry("rate limited".into())]));
+    assert_eq!(process_due(&api, &db_client).await, Ok(1));
+    let pending = store.pending_role_jobs();
+    assert_eq!(pending[0].attempts, 1);
+    assert_eq!(pending[0].last_error.as_deref(), Some("rate limited"));
+    // backing off
+    assert_eq!(process_due(&api, &db_client).await, Ok(0));
+
+    record("2").await;
+    let api = Flaky(Mutex::new(vec![ApplyError::Permanent("Missing Permissions".into())]));
+    assert_eq!(process_due(&api, &db_client).await, Ok(1));
+    let failed = db_client.failed_role_jobs().await.unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].user_id, "2");
+
+    assert_eq!(db_client.retry_failed_role_jobs().await, Ok(1));
+    assert_eq!(process_due(&api, &db_client).await, Ok(1));
+    assert_eq!(store.pending_role_jobs().len(), 1);
+}
+
+#[test]
+fn test_backoff() {
+    assert_eq!(backoff(1), Some(BASE_DELAY));
+    assert_eq!(backoff(3), Some(BASE_DELAY * 4));
+    assert_eq!(backoff(MAX_ATTEMPTS - 1), Some(MAX_DELAY.min(BASE_DELAY * 64)));
+    assert_eq!(backoff(MAX_ATTEMPTS), None);
+}
+
+#[tokio::test]
+async fn test_claimed_jobs_are_not_claimed_again() {
+    use crate::data::{MemoryStore, WalletStore};
+
+    let store = Arc::new(MemoryStore::new());
+    let db_client = DbClient::new(store.clone());
+    let blank = String::new;
+    store
+        .insert_non_signed("1".into(), blank(), "kanaria".into(), "Kusama".into(), blank(), blank(), blank(), Some(7))
+        .await;
+
+    // an expired lease hands the job to the next worker
+    assert_eq!(db_client.claim_role_jobs(BATCH_SIZE, Duration::ZERO).await.unwrap().len(), 1);
+    assert_eq!(db_client.claim_role_jobs(BATCH_SIZE, LEASE).await.unwrap().len(), 1);
+    assert_eq!(db_client.claim_role_jobs(BATCH_SIZE, LEASE).await, Ok(vec![]));
+}
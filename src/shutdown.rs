@@ -0,0 +1,94 @@
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::time::{sleep, Instant};
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Counts the interactions being handled, so a shutdown can wait for them to finish.
+#[derive(Default)]
+pub struct InFlight {
+    stopping: AtomicBool,
+    count: AtomicUsize,
+}
+
+/// Marks an interaction as finished when dropped.
+pub struct Guard<'a>(&'a InFlight);
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        self.0.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new interaction, `None` once a shutdown has started.
+    pub fn start(&self) -> Option<Guard<'_>> {
+        // counted before checking, so drain never misses an interaction that got through
+        self.count.fetch_add(1, Ordering::SeqCst);
+        let guard = Guard(self);
+        if self.stopping.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Stops accepting interactions and waits for the running ones, returns false
+    /// when some were still running after `timeout`.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.stopping.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + timeout;
+        while self.in_flight() > 0 {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            sleep(DRAIN_POLL_INTERVAL).await;
+        }
+        true
+    }
+}
+
+/// Resolves on SIGTERM or SIGINT (Ctrl-C).
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("Cannot listen for SIGTERM");
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.ok();
+}
+
+#[tokio::test]
+async fn test_drain_waits_for_in_flight() {
+    use std::sync::Arc;
+
+    let in_flight = Arc::new(InFlight::new());
+    let running = in_flight.clone();
+    let handler = tokio::spawn(async move {
+        let _guard = running.start().unwrap();
+        sleep(Duration::from_millis(100)).await;
+    });
+    sleep(Duration::from_millis(10)).await;
+
+    assert!(!in_flight.drain(Duration::from_millis(20)).await);
+    assert!(in_flight.start().is_none());
+    assert!(in_flight.drain(Duration::from_secs(5)).await);
+    handler.await.unwrap();
+    assert_eq!(in_flight.in_flight(), 0);
+}
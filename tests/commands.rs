@@ -0,0 +1,417 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use ethereum_types::{H160, U256};
+use sp_core::crypto::AccountId32;
+
+use exosama_wallet_collector::{
+    bot::Handler,
+    chain::{ChainClient, ChainError, Eligibility, Rule},
+    config::Config,
+    data::{Campaign, DbClient, MemoryStore},
+    wallet::{self, CommandError, ErrorKind, Member, RegisterRequest, Registered, SignRequest},
+};
+
+const KSM: &str = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+const GLMR: &str = "0xb794f5ea0ba39494ce839613fffba74279579268";
+const SIGNATURE: &str = "fb275c30af9eceb9e0370f80896c223fdc728e590bc5deefb776f78ac914c8b3be21800a9f959bbb7e03ce4b745965c82261dfbcc3d7c7906a9bd7a4f855380a";
+// a MetaMask `personal_sign` of KSM by the key of EVM_SIGNER
+const EVM_SIGNER: &str = "0x17c5185167401ed00cf5f5b2fc97d9bbfdb7d025";
+const EVM_SIGNATURE: &str = "a946bb091e46cd9cc8dedf067b49a2d2318b37f21cfd34a0d1db8c17242a1f105a878d5b68e5cd98e64e0a60669f42f6c2ce787c0d00f1bbd5a4246ff25f7e4f1c";
+const PRE_ROLE: &str = "Verified";
+const POST_ROLE: u64 = 123456789;
+
+fn campaign(name: &str) -> Campaign {
+    Campaign {
+        name: name.to_string(),
+        whitelist: true,
+        required_roles: vec![],
+        chains: vec![
+            "Kusama".to_string(),
+            "Moonbeam".to_string(),
+            "Moonriver".to_string(),
+        ],
+        open: true,
+        evm_signatures: false,
+    }
+}
+
+fn setup(eligibility: Eligibility) -> (Arc<MemoryStore>, Handler) {
+    let store = Arc::new(MemoryStore::new());
+    store.add_campaign(campaign("kanaria"));
+    store.add_to_whitelist("kanaria", KSM);
+    let config = Config {
+        pre_role: PRE_ROLE.to_string(),
+        post_role_id: POST_ROLE,
+        ss58_format: 2,
+        ..Config::default()
+    };
+    let handler = Handler::new(config, DbClient::new(store.clone()), eligibility);
+    (store, handler)
+}
+
+fn member(roles: &[&str]) -> Member {
+    Member {
+        user_id: "42".to_string(),
+        user_tag: "user#0042".to_string(),
+        avatar: String::new(),
+        roles: roles.iter().map(|r| r.to_string()).collect(),
+    }
+}
+
+fn sign_request(campaign: &str) -> SignRequest {
+    SignRequest {
+        kusama_address: KSM.to_string(),
+        moonbeam_address: GLMR.to_string(),
+        signature: SIGNATURE.to_string(),
+        campaign: campaign.to_string(),
+        multisig: None,
+    }
+}
+
+fn register_request(address_type: &str, address: &str) -> RegisterRequest {
+    RegisterRequest {
+        address_type: Some(address_type.to_string()),
+        address: address.to_string(),
+        campaign: "kanaria".to_string(),
+    }
+}
+
+fn no_rules() -> Eligibility {
+    Eligibility::new(None, Duration::from_secs(60))
+}
+
+#[tokio::test]
+async fn sign_records_whitelisted_address() {
+    let (store, handler) = setup(no_rules());
+
+    let result = wallet::sign(&handler, &member(&[]), sign_request("kanaria")).await;
+    assert_eq!(result, Ok(()));
+
+    let signed = store.signed();
+    assert_eq!(signed.len(), 1);
+    assert_eq!(signed[0].campaign, "kanaria");
+    assert_eq!(signed[0].ksm_address, KSM);
+    assert_eq!(signed[0].glmr_address, GLMR);
+}
+
+#[tokio::test]
+async fn sign_rejects_address_missing_from_whitelist() {
+    let (store, handler) = setup(no_rules());
+    store.add_campaign(campaign("skybreach"));
+
+    let result = wallet::sign(&handler, &member(&[]), sign_request("skybreach")).await;
+    assert_eq!(
+        result,
+        Err(CommandError::new(
+            ErrorKind::Whitelist,
+            "Address is not on the skybreach whitelist"
+        ))
+    );
+    assert!(store.signed().is_empty());
+}
+
+#[tokio::test]
+async fn sign_is_gated_by_campaign_roles_and_window() {
+    let (store, handler) = setup(no_rules());
+    let mut gated = campaign("gated");
+    gated.whitelist = false;
+    gated.required_roles = vec!["Pioneer".to_string()];
+    store.add_campaign(gated.clone());
+
+    let result = wallet::sign(&handler, &member(&["Visitor"]), sign_request("gated")).await;
+    assert_eq!(result.unwrap_err().kind, ErrorKind::Role);
+    assert!(wallet::sign(&handler, &member(&["Pioneer"]), sign_request("gated"))
+        .await
+        .is_ok());
+
+    gated.open = false;
+    store.add_campaign(gated);
+    let result = wallet::sign(&handler, &member(&["Pioneer"]), sign_request("gated")).await;
+    assert_eq!(
+        result,
+        Err(CommandError::new(ErrorKind::Campaign, "The gated campaign is not open"))
+    );
+    assert_eq!(store.signed().len(), 1);
+}
+
+#[tokio::test]
+async fn register_requires_pre_role() {
+    let (store, handler) = setup(no_rules());
+
+    let result = wallet::register(&handler, &member(&[]), register_request("Kusama", KSM)).await;
+    assert!(result.is_err());
+    assert!(store.registrations().is_empty());
+}
+
+#[tokio::test]
+async fn register_grants_role_for_kusama_wallets_only() {
+    let (store, handler) = setup(no_rules());
+    let member = member(&[PRE_ROLE]);
+
+    let result = wallet::register(&handler, &member, register_request("Kusama", KSM)).await;
+    assert_eq!(result, Ok(Registered::GrantRole(POST_ROLE)));
+
+    let result = wallet::register(&handler, &member, register_request("Moonbeam", GLMR)).await;
+    assert_eq!(result, Ok(Registered::Recorded));
+
+    // registering again replaces the earlier wallet of the same type
+    let other = "0x0000000000000000000000000000000000000001";
+    let result = wallet::register(&handler, &member, register_request("Moonbeam", other)).await;
+    assert_eq!(result, Ok(Registered::Recorded));
+
+    let registrations = store.registrations();
+    assert_eq!(registrations.len(), 2);
+    assert!(registrations
+        .iter()
+        .any(|r| r.address_type == "Moonbeam" && r.address == other));
+}
+
+#[tokio::test]
+async fn register_does_not_need_a_whitelist_entry() {
+    let (store, handler) = setup(no_rules());
+    let unlisted = "HNZata7iMYWmk5RvZRTiAsSDhV8366zq2YGb3tLH5Upf74F";
+
+    let result = wallet::register(&handler, &member(&[PRE_ROLE]), register_request("Kusama", unlisted)).await;
+    assert_eq!(result, Ok(Registered::GrantRole(POST_ROLE)));
+    assert_eq!(store.registrations().len(), 1);
+}
+
+#[tokio::test]
+async fn register_rejects_invalid_and_unaccepted_wallets() {
+    let (store, handler) = setup(no_rules());
+    let mut kusama_only = campaign("kanaria");
+    kusama_only.chains = vec!["Kusama".to_string()];
+    store.add_campaign(kusama_only);
+    let member = member(&[PRE_ROLE]);
+
+    let result = wallet::register(&handler, &member, register_request("Moonbeam", GLMR)).await;
+    assert_eq!(
+        result,
+        Err(CommandError::new(
+            ErrorKind::Campaign,
+            "The kanaria campaign does not accept Moonbeam wallets"
+        ))
+    );
+
+    let result = wallet::register(&handler, &member, register_request("Kusama", GLMR)).await;
+    assert_eq!(result.unwrap_err().kind, ErrorKind::Address);
+    assert!(store.registrations().is_empty());
+}
+
+struct FixedBalance(u128);
+
+#[async_trait]
+impl ChainClient for FixedBalance {
+    async fn free_balance(&self, _account: &AccountId32) -> Result<u128, ChainError> {
+        Ok(self.0)
+    }
+
+    async fn erc721_balance(&self, _contract: &H160, _owner: &H160) -> Result<U256, ChainError> {
+        Ok(U256::zero())
+    }
+}
+
+#[tokio::test]
+async fn sign_checks_on_chain_rules() {
+    let eligibility = Eligibility::new(Some(Box::new(FixedBalance(5))), Duration::from_secs(60));
+    let (store, handler) = setup(eligibility);
+    store.add_rule("kanaria", Rule::SubstrateBalance { min: 10 });
+
+    let result = wallet::sign(&handler, &member(&[]), sign_request("kanaria")).await;
+    assert_eq!(result.unwrap_err().kind, ErrorKind::Eligibility);
+    assert!(store.signed().is_empty());
+}
+
+#[tokio::test]
+async fn dry_run_reports_each_step_without_recording() {
+    let (store, handler) = setup(no_rules());
+
+    let checks = wallet::dry_run(&handler, &member(&[]), sign_request("kanaria")).await;
+    let steps = checks.iter().map(|check| check.step).collect::<Vec<_>>();
+    assert_eq!(
+        steps,
+        vec!["Campaign", "Kusama address", "Moonbeam address", "Signature", "Whitelist", "Eligibility"]
+    );
+    assert!(checks.iter().all(|check| check.passed()), "{:?}", checks);
+    assert_eq!(checks[1].result, Some(Ok("Kusama format".to_string())));
+    assert!(store.signed().is_empty());
+
+    let mut request = sign_request("kanaria");
+    request.signature = SIGNATURE[..126].to_string();
+    let checks = wallet::dry_run(&handler, &member(&[]), request).await;
+    let why = checks[3].result.clone().unwrap().unwrap_err();
+    assert!(why.contains("The signature is 63 bytes, expected 64"), "{}", why);
+    assert!(checks[4].passed());
+
+    let mut request = sign_request("kanaria");
+    request.kusama_address = "not an address".to_string();
+    let checks = wallet::dry_run(&handler, &member(&[]), request).await;
+    assert!(!checks[1].passed());
+    assert_eq!(checks[3].result, None);
+    assert_eq!(checks[4].result, None);
+    assert!(store.signed().is_empty());
+}
+
+#[tokio::test]
+async fn register_detects_the_wallet_type() {
+    let (store, handler) = setup(no_rules());
+    let member = member(&[PRE_ROLE]);
+    let untyped = |address: &str| RegisterRequest {
+        address_type: None,
+        ..register_request("", address)
+    };
+
+    let result = wallet::register(&handler, &member, untyped(KSM)).await;
+    assert_eq!(result, Ok(Registered::GrantRole(POST_ROLE)));
+
+    // the default campaign takes both EVM chains, so the type has to be picked
+    let result = wallet::register(&handler, &member, untyped(GLMR)).await;
+    assert_eq!(result.unwrap_err().kind, ErrorKind::Input);
+
+    let mut moonbeam_only = campaign("kanaria");
+    moonbeam_only.chains = vec!["Kusama".to_string(), "Moonbeam".to_string()];
+    store.add_campaign(moonbeam_only);
+    let result = wallet::register(&handler, &member, untyped(GLMR)).await;
+    assert_eq!(result, Ok(Registered::Recorded));
+    assert!(store
+        .registrations()
+        .iter()
+        .any(|r| r.address_type == "Moonbeam" && r.address == GLMR));
+
+    let result = wallet::register(&handler, &member, untyped("cosmos is not here")).await;
+    assert_eq!(
+        result,
+        Err(CommandError::new(
+            ErrorKind::Address,
+            "The wallet type cannot be detected, it contains whitespace"
+        ))
+    );
+}
+
+#[tokio::test]
+async fn address_errors_suggest_fixes() {
+    let (store, handler) = setup(no_rules());
+
+    let mut request = sign_request("kanaria");
+    request.kusama_address = GLMR.to_string();
+    request.moonbeam_address = KSM.to_string();
+    let result = wallet::sign(&handler, &member(&[]), request).await;
+    assert_eq!(
+        result,
+        Err(CommandError::new(
+            ErrorKind::Address,
+            "The Kusama and Moonbeam addresses are swapped"
+        ))
+    );
+
+    let mut request = sign_request("kanaria");
+    request.moonbeam_address = KSM.to_string();
+    let result = wallet::sign(&handler, &member(&[]), request).await;
+    assert_eq!(
+        result.unwrap_err().message,
+        "Invalid GLMR address, it is a Kusama SS58 address"
+    );
+
+    let result = wallet::register(&handler, &member(&[PRE_ROLE]), register_request("Kusama", GLMR)).await;
+    assert_eq!(
+        result.unwrap_err().message,
+        "Invalid ss58 address provided, it is an H160 address"
+    );
+
+    // the same account in the Polkadot format is not taken for the Kusama address
+    let polkadot = wallet_verify::encode_ss58(&wallet_verify::check_ss58(KSM).unwrap().account, 0);
+    let mut request = sign_request("kanaria");
+    request.kusama_address = polkadot.clone();
+    let result = wallet::sign(&handler, &member(&[]), request).await;
+    assert_eq!(
+        result.unwrap_err().message,
+        format!(
+            "Invalid KSM address, it is a Polkadot SS58 address, the Kusama address of the same account is {}",
+            KSM
+        )
+    );
+    let result = wallet::register(&handler, &member(&[PRE_ROLE]), register_request("Kusama", &polkadot)).await;
+    assert_eq!(
+        result.unwrap_err().message,
+        format!(
+            "The address is not a Kusama address, it is a Polkadot SS58 address, the Kusama address of the same account is {}",
+            KSM
+        )
+    );
+    assert!(store.signed().is_empty());
+    assert!(store.registrations().is_empty());
+}
+
+#[tokio::test]
+async fn metamask_signatures_need_opt_in_and_an_unclaimed_address() {
+    let (store, handler) = setup(no_rules());
+    let evm_request = || SignRequest {
+        moonbeam_address: EVM_SIGNER.to_string(),
+        signature: EVM_SIGNATURE.to_string(),
+        ..sign_request("kanaria")
+    };
+
+    let result = wallet::sign(&handler, &member(&[]), evm_request()).await;
+    assert_eq!(
+        result,
+        Err(CommandError::new(
+            ErrorKind::Signature,
+            "The kanaria campaign does not accept MetaMask signatures, sign the Moonbeam address with the Kusama account."
+        ))
+    );
+
+    store.add_campaign(Campaign {
+        evm_signatures: true,
+        ..campaign("kanaria")
+    });
+    assert_eq!(wallet::sign(&handler, &member(&[]), evm_request()).await, Ok(()));
+    assert_eq!(store.signed()[0].proof.as_ref().unwrap().scheme, "eip191");
+    // signing again for the same address is fine
+    assert_eq!(wallet::sign(&handler, &member(&[]), evm_request()).await, Ok(()));
+
+    // the Kusama key proves the address whoever signed for it before
+    let owner = Member {
+        user_id: "7".to_string(),
+        ..member(&[])
+    };
+    assert_eq!(wallet::sign(&handler, &owner, sign_request("kanaria")).await, Ok(()));
+    let other = Member {
+        user_id: "8".to_string(),
+        ..member(&[])
+    };
+    let result = wallet::sign(&handler, &other, evm_request()).await;
+    assert_eq!(result.unwrap_err().kind, ErrorKind::Signature);
+    assert_eq!(store.signed().len(), 2);
+}
+
+#[tokio::test]
+async fn public_keys_are_recorded_as_addresses() {
+    let (store, handler) = setup(no_rules());
+    let public_key = format!("0x{}", hex::encode(wallet_verify::check_ss58(KSM).unwrap().account));
+
+    let mut request = sign_request("kanaria");
+    request.kusama_address = public_key.clone();
+    assert_eq!(request.normalize(2), Some(KSM.to_string()));
+    assert_eq!(request.normalize(2), None);
+    assert_eq!(wallet::sign(&handler, &member(&[]), request).await, Ok(()));
+    assert_eq!(store.signed()[0].ksm_address, KSM);
+
+    let mut request = RegisterRequest {
+        address_type: None,
+        ..register_request("", &public_key)
+    };
+    assert_eq!(request.normalize(2), Some(KSM.to_string()));
+    let result = wallet::register(&handler, &member(&[PRE_ROLE]), request).await;
+    assert_eq!(result, Ok(Registered::GrantRole(POST_ROLE)));
+    assert_eq!(store.registrations()[0].address, KSM);
+
+    // only Kusama wallets are converted, and only keys with 0x
+    let mut request = register_request("Moonbeam", &public_key);
+    assert_eq!(request.normalize(2), None);
+    assert_eq!(wallet::normalize_ss58(&public_key[2..], 2), &public_key[2..]);
+    assert_eq!(
+        wallet::normalize_ss58(&public_key, 0),
+        wallet_verify::encode_ss58(&wallet_verify::check_ss58(KSM).unwrap().account, 0)
+    );
+}
@@ -0,0 +1,162 @@
+use base58::{FromBase58, ToBase58};
+use blake2_rfc::blake2b::blake2b;
+
+use crate::error::{AddressError, Ss58Error};
+
+const SS58_PREFIX: &[u8] = b"SS58PRE";
+const CHECKSUM_LEN: usize = 2;
+
+/// A decoded SS58 address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ss58Address {
+    /// The account id, the public key for sr25519 and ed25519 accounts.
+    pub account: [u8; 32],
+    /// The network prefix, e.g. 2 for Kusama.
+    pub prefix: u16,
+}
+
+fn ss58_hash(data: &[u8]) -> Vec<u8> {
+    let mut input = SS58_PREFIX.to_vec();
+    input.extend_from_slice(data);
+    blake2b(64, &[], &input).as_bytes().to_vec()
+}
+
+/// Decodes an SS58 account address of any network.
+pub fn decode_ss58(address: &str) -> Result<Ss58Address, Ss58Error> {
+    let data = address.from_base58().map_err(|_| Ss58Error::NotBase58)?;
+    if data.len() < 2 {
+        return Err(Ss58Error::Length(data.len()));
+    }
+    // prefixes up to 63 take one byte, up to 16383 two, with the bits spread as below
+    let (prefix_len, prefix) = match data[0] {
+        0..=63 => (1, u16::from(data[0])),
+        64..=127 => {
+            let lower = (data[0] << 2) | (data[1] >> 6);
+            let upper = data[1] & 0b0011_1111;
+            (2, u16::from(lower) | (u16::from(upper) << 8))
+        }
+        _ => return Err(Ss58Error::Prefix),
+    };
+    if data.len() != prefix_len + 32 + CHECKSUM_LEN {
+        return Err(Ss58Error::Length(data.len()));
+    }
+
+    let body = &data[..prefix_len + 32];
+    if ss58_hash(body)[..CHECKSUM_LEN] != data[prefix_len + 32..] {
+        return Err(Ss58Error::Checksum);
+    }
+    let mut account = [0; 32];
+    account.copy_from_slice(&body[prefix_len..]);
+    Ok(Ss58Address { account, prefix })
+}
+
+/// Encodes an account id as an SS58 address of the network with the given prefix, which must be
+/// below 16384.
+pub fn encode_ss58(account: &[u8; 32], prefix: u16) -> String {
+    let prefix = prefix & 0b0011_1111_1111_1111;
+    let mut data = match prefix {
+        0..=63 => vec![prefix as u8],
+        _ => {
+            let first = ((prefix & 0b0000_0000_1111_1100) as u8) >> 2;
+            let second = ((prefix >> 8) as u8) | ((prefix & 0b0000_0000_0000_0011) as u8) << 6;
+            vec![first | 0b0100_0000, second]
+        }
+    };
+    data.extend_from_slice(account);
+    let checksum = ss58_hash(&data);
+    data.extend_from_slice(&checksum[..CHECKSUM_LEN]);
+    data.to_base58()
+}
+
+/// Checks an SS58 address of any network.
+pub fn check_ss58(address: &str) -> Result<Ss58Address, AddressError> {
+    decode_ss58(address).map_err(AddressError::Ss58)
+}
+
+/// Checks an SS58 address of the network with the given prefix. The same account encoded for
+/// another network is rejected, so a Polkadot address does not pass for a Kusama one.
+pub fn check_ss58_network(address: &str, prefix: u16) -> Result<Ss58Address, AddressError> {
+    let decoded = check_ss58(address)?;
+    if decoded.prefix != prefix {
+        return Err(AddressError::Network {
+            expected: prefix,
+            found: decoded.prefix,
+        });
+    }
+    Ok(decoded)
+}
+
+/// Checks an ethereum style address, 20 hex encoded bytes with or without `0x`.
+pub fn check_h160(address: &str) -> Result<[u8; 20], AddressError> {
+    let address = address.strip_prefix("0x").unwrap_or(address);
+    let mut h160 = [0; 20];
+    hex::decode_to_slice(address, &mut h160).map_err(|_| AddressError::H160)?;
+    Ok(h160)
+}
+
+/// Checks the address of a wallet for the chain: SS58 of the `ss58_format` network for Kusama,
+/// 2 outside of test networks, H160 for Moonbeam and Moonriver.
+pub fn verify(chain: &str, address: &str, ss58_format: u16) -> Result<(), AddressError> {
+    match chain {
+        "Moonbeam" | "Moonriver" => check_h160(address).map(|_| ()),
+        "Kusama" => check_ss58_network(address, ss58_format).map(|_| ()),
+        other => Err(AddressError::UnsupportedChain(other.to_string())),
+    }
+}
+
+#[test]
+fn test_ss58_round_trip() {
+    let address = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+    let decoded = check_ss58(address).unwrap();
+    assert_eq!(decoded.prefix, 2);
+    assert_eq!(encode_ss58(&decoded.account, 2), address);
+
+    // agrees with substrate, two byte prefixes included
+    use sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+    for prefix in [0, 2, 42, 1284] {
+        let expected = AccountId32::new(decoded.account).to_ss58check_with_version(Ss58AddressFormat::custom(prefix));
+        assert_eq!(encode_ss58(&decoded.account, prefix), expected);
+        assert_eq!(decode_ss58(&expected).unwrap().prefix, prefix);
+    }
+
+    assert_eq!(decode_ss58("0xb794f5ea"), Err(Ss58Error::NotBase58));
+    assert_eq!(decode_ss58("EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiy"), Err(Ss58Error::Length(34)));
+    assert_eq!(decode_ss58("EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyv"), Err(Ss58Error::Checksum));
+}
+
+#[test]
+fn test_addresses_by_chain() {
+    let kusama = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+    assert!(verify("Kusama", kusama, 2).is_ok());
+    assert!(verify("Moonbeam", "0xb794f5ea0ba39494ce839613fffba74279579268", 2).is_ok());
+    assert!(verify("Moonriver", "b794f5ea0ba39494ce839613fffba74279579268", 2).is_ok());
+    assert_eq!(
+        verify("Moonbeam", "0xb794f5ea0ba39494ce839613fffba742795792", 2),
+        Err(AddressError::H160)
+    );
+    assert_eq!(
+        verify("Kusama", "0xb794f5ea0ba39494ce839613fffba74279579268", 2).unwrap_err().to_string(),
+        "Invalid ss58 address provided"
+    );
+    assert_eq!(
+        verify("Polkadot", "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5", 0),
+        Err(AddressError::UnsupportedChain("Polkadot".to_string()))
+    );
+
+    // the same account on other networks is not a Kusama address
+    let account = check_ss58(kusama).unwrap().account;
+    for prefix in [0, 42, 1284] {
+        let other = encode_ss58(&account, prefix);
+        assert_eq!(
+            verify("Kusama", &other, 2),
+            Err(AddressError::Network { expected: 2, found: prefix })
+        );
+    }
+    assert_eq!(
+        verify("Kusama", &encode_ss58(&account, 0), 2).unwrap_err().to_string(),
+        "The address is not a Kusama address"
+    );
+    // a test network configures its own prefix
+    assert!(verify("Kusama", &encode_ss58(&account, 42), 42).is_ok());
+    assert!(verify("Kusama", kusama, 42).is_err());
+}
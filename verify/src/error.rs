@@ -0,0 +1,142 @@
+use std::fmt;
+
+use crate::sniff::network_name;
+
+/// Why an SS58 address could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ss58Error {
+    NotBase58,
+    /// The decoded length, an account address is 35 or 36 bytes.
+    Length(usize),
+    /// The network prefix is outside the ranges SS58 defines.
+    Prefix,
+    Checksum,
+}
+
+impl fmt::Display for Ss58Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ss58Error::NotBase58 => f.write_str("not valid base58"),
+            Ss58Error::Length(len) => write!(f, "decodes to {} bytes instead of 35 or 36", len),
+            Ss58Error::Prefix => f.write_str("invalid network prefix"),
+            Ss58Error::Checksum => f.write_str("checksum mismatch"),
+        }
+    }
+}
+
+/// A rejected address. The messages are shown to users as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    Ss58(Ss58Error),
+    /// A valid SS58 address of another network than the expected one.
+    Network { expected: u16, found: u16 },
+    H160,
+    /// The chain is not one wallets are collected for.
+    UnsupportedChain(String),
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Ss58(_) => f.write_str("Invalid ss58 address provided"),
+            AddressError::Network { expected, .. } => match network_name(*expected) {
+                Some(network) => write!(f, "The address is not a {} address", network),
+                None => write!(f, "The address is not an address of network {}", expected),
+            },
+            AddressError::H160 => f.write_str("Invalid H160 address provided"),
+            AddressError::UnsupportedChain(_) => f.write_str("The provided wallet address is invalid."),
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+/// A rejected signature. The messages are shown to users as they are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    InvalidSs58(Ss58Error),
+    InvalidH160,
+    /// Neither hex nor base64.
+    Encoding,
+    /// A character of neither encoding.
+    Character(char),
+    /// Hex with a digit missing, the number of digits is given.
+    OddHexLength(usize),
+    /// The decoded length, which no scheme uses.
+    Length(usize),
+    /// A 66 byte `MultiSignature` whose type byte is not the ECDSA one.
+    MultiSignatureType(u8),
+    /// The bytes are not a signature of the scheme, e.g. the wrong length.
+    Malformed,
+    InvalidPublicKey,
+    NotVerified,
+    /// A `personal_sign` signature recovered to another address.
+    WrongSigner,
+    InvalidSignatory(String),
+    TooFewSignatories,
+    DuplicateSignatories,
+    /// The threshold is zero or above the number of signatories, which is given.
+    InvalidThreshold(usize),
+    MultisigMismatch,
+    /// Multisig accounts are signed for by a signatory, not with MetaMask.
+    MultisigEvm,
+    NoSignatory,
+    SignerNotSignatory,
+    /// A proof's message is not the one for the addresses.
+    MessageMismatch,
+    UnknownScheme(String),
+    UnknownFormat(String),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::InvalidSs58(_) => f.write_str("Input substrate address not valid."),
+            SignatureError::InvalidH160 => f.write_str("GLMR address is not valid"),
+            SignatureError::Encoding => f.write_str("The signature is neither hex nor base64."),
+            SignatureError::Character(c) => {
+                write!(f, "The signature contains '{}', which is neither hex nor base64.", c)
+            }
+            SignatureError::OddHexLength(digits) => write!(
+                f,
+                "The signature has an odd number of hex digits ({}), a digit is missing.",
+                digits
+            ),
+            SignatureError::Length(len) => write!(
+                f,
+                "The signature is {} bytes, expected 64, or 65 for MetaMask.",
+                len
+            ),
+            SignatureError::MultiSignatureType(byte) => write!(
+                f,
+                "A 66 byte signature must be an ECDSA MultiSignature starting with 0x02, not 0x{:02x}.",
+                byte
+            ),
+            SignatureError::Malformed => f.write_str("Input signature could not be parsed."),
+            SignatureError::InvalidPublicKey => {
+                f.write_str("Something went wrong while trying to parse substrate address.")
+            }
+            SignatureError::NotVerified => f.write_str("Signature could not be verified."),
+            SignatureError::WrongSigner => f.write_str("Signature was not made by the GLMR address."),
+            SignatureError::InvalidSignatory(address) => write!(f, "Invalid signatory address {}", address),
+            SignatureError::TooFewSignatories => f.write_str("A multisig account needs at least two signatories"),
+            SignatureError::DuplicateSignatories => f.write_str("The signatories must be different accounts"),
+            SignatureError::InvalidThreshold(signatories) => write!(
+                f,
+                "The threshold must be between 1 and the number of signatories ({})",
+                signatories
+            ),
+            SignatureError::MultisigMismatch => {
+                f.write_str("The signatories and threshold do not match the KSM address")
+            }
+            SignatureError::MultisigEvm => f.write_str("Sign your GLMR address with one of the signatories"),
+            SignatureError::NoSignatory => f.write_str("None of the signatories signed the GLMR address"),
+            SignatureError::SignerNotSignatory => f.write_str("The signer is not one of the signatories"),
+            SignatureError::MessageMismatch => f.write_str("The message does not match the addresses"),
+            SignatureError::UnknownScheme(scheme) => write!(f, "Unknown signature scheme {}", scheme),
+            SignatureError::UnknownFormat(format) => write!(f, "Unknown message format {}", format),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
@@ -0,0 +1,33 @@
+//! Address and signature checks linking a substrate account to an ethereum style (H160) address.
+//!
+//! These are the rules the wallet collector bot applies to `/sign` and `/wallet`, kept free of
+//! Discord and database code so that other services, including wasm32 builds, accept exactly
+//! the same wallets.
+//!
+//! ```
+//! use wallet_verify::{check_signature, Scheme};
+//!
+//! let proof = check_signature(
+//!     "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu",
+//!     "0xb794f5ea0ba39494ce839613fffba74279579268",
+//!     "0xfb275c30af9eceb9e0370f80896c223fdc728e590bc5deefb776f78ac914c8b3be21800a9f959bbb7e03ce4b745965c82261dfbcc3d7c7906a9bd7a4f855380a",
+//! )
+//! .unwrap();
+//! assert_eq!(proof.scheme, Scheme::Ed25519);
+//! ```
+
+mod address;
+mod error;
+mod multisig;
+mod signature;
+mod sniff;
+
+pub use address::{check_h160, check_ss58, check_ss58_network, decode_ss58, encode_ss58, verify, Ss58Address};
+pub use error::{AddressError, SignatureError, Ss58Error};
+pub use multisig::{check_multisig_signature, multisig_account_id, Multisig, MultisigProof};
+pub use signature::{
+    check_ecdsa_signature, check_ed_signature, check_evm_signature, check_signature, check_ss58_signature,
+    decode_signature, diagnose_signature, multi_signature, personal_message_hash, verify_proof, Attempt, MessageFormat,
+    Proof, Scheme, EVM_SIGNATURE_LEN, MESSAGE_FORMATS, SIGNATURE_LEN,
+};
+pub use sniff::{network_name, sniff_address, AddressKind, UnknownAddress};
@@ -0,0 +1,254 @@
+use blake2_rfc::blake2b::blake2b;
+
+use crate::address::decode_ss58;
+use crate::error::SignatureError;
+use crate::signature::{check_signature, decode_signature, multi_signature, Proof, Scheme, EVM_SIGNATURE_LEN};
+
+const MULTISIG_PREFIX: &[u8] = b"modlpy/utilisuba";
+
+/// The parameters of a pallet-multisig account, which one of the signatories signs for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multisig {
+    /// SS58 addresses of every signatory, in any order.
+    pub signatories: Vec<String>,
+    pub threshold: u16,
+}
+
+/// The multisig account behind an SS58 address and the signatory who signed for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigProof {
+    pub signer: String,
+    pub signatories: Vec<String>,
+    pub threshold: u16,
+}
+
+/// The account id pallet-multisig derives for the signatories and threshold: the blake2 hash of
+/// the SCALE encoded prefix, sorted signatories and threshold.
+pub fn multisig_account_id(signatories: &[[u8; 32]], threshold: u16) -> [u8; 32] {
+    let mut sorted = signatories.to_vec();
+    sorted.sort_unstable();
+
+    let mut encoded = MULTISIG_PREFIX.to_vec();
+    encoded.extend(compact_len(sorted.len()));
+    for signatory in &sorted {
+        encoded.extend_from_slice(signatory);
+    }
+    encoded.extend_from_slice(&threshold.to_le_bytes());
+
+    let mut account = [0; 32];
+    account.copy_from_slice(blake2b(32, &[], &encoded).as_bytes());
+    account
+}
+
+/// SCALE compact encoding of a length, enough for any number of signatories the pallet allows.
+fn compact_len(len: usize) -> Vec<u8> {
+    if len < 1 << 6 {
+        vec![(len as u8) << 2]
+    } else {
+        (((len as u16) << 2) | 1).to_le_bytes().to_vec()
+    }
+}
+
+impl Multisig {
+    /// Checks the signatories and threshold, returning the account id they derive.
+    pub fn account_id(&self) -> Result<[u8; 32], SignatureError> {
+        let signatories = self
+            .signatories
+            .iter()
+            .map(|signatory| {
+                decode_ss58(signatory)
+                    .map(|address| address.account)
+                    .map_err(|_| SignatureError::InvalidSignatory(signatory.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if signatories.len() < 2 || signatories.len() >= 1 << 14 {
+            return Err(SignatureError::TooFewSignatories);
+        }
+        let mut unique = signatories.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        if unique.len() != signatories.len() {
+            return Err(SignatureError::DuplicateSignatories);
+        }
+        if self.threshold == 0 || usize::from(self.threshold) > signatories.len() {
+            return Err(SignatureError::InvalidThreshold(signatories.len()));
+        }
+        Ok(multisig_account_id(&signatories, self.threshold))
+    }
+}
+
+/// Checks that the signatories and threshold derive the multisig SS58 address and that one of
+/// the signatories signed the H160 address.
+pub fn check_multisig_signature(
+    ss58_add: &str,
+    h160_add: &str,
+    signature: &str,
+    multisig: &Multisig,
+) -> Result<Proof, SignatureError> {
+    let acc = decode_ss58(ss58_add).map_err(SignatureError::InvalidSs58)?;
+    if multisig.account_id()? != acc.account {
+        return Err(SignatureError::MultisigMismatch);
+    }
+
+    let sig = decode_signature(signature)?;
+    if sig.len() == EVM_SIGNATURE_LEN && multi_signature(&sig).is_none() {
+        return Err(SignatureError::MultisigEvm);
+    }
+
+    // a `personal_sign` signature whose first byte is 0 or 1 also decodes as a `MultiSignature`,
+    // so the length check above is not enough: only the EVM key signed it, never a signatory
+    let mut evm_signed = false;
+    for signer in &multisig.signatories {
+        match check_signature(signer, h160_add, signature) {
+            Ok(proof) if proof.scheme == Scheme::Eip191 => evm_signed = true,
+            Ok(mut proof) => {
+                proof.multisig = Some(MultisigProof {
+                    signer: signer.to_string(),
+                    signatories: multisig.signatories.clone(),
+                    threshold: multisig.threshold,
+                });
+                return Ok(proof);
+            }
+            Err(_) => (),
+        }
+    }
+    if evm_signed {
+        return Err(SignatureError::MultisigEvm);
+    }
+    Err(SignatureError::NoSignatory)
+}
+
+impl MultisigProof {
+    /// Checks the multisig part of a proof again along with the signer's signature.
+    pub(crate) fn verify(&self, ss58_add: &str, h160_add: &str, proof: &Proof) -> Result<(), SignatureError> {
+        let multisig = Multisig {
+            signatories: self.signatories.clone(),
+            threshold: self.threshold,
+        };
+        let acc = decode_ss58(ss58_add).map_err(SignatureError::InvalidSs58)?;
+        if multisig.account_id()? != acc.account {
+            return Err(SignatureError::MultisigMismatch);
+        }
+        if !self.signatories.contains(&self.signer) {
+            return Err(SignatureError::SignerNotSignatory);
+        }
+        if proof.scheme == Scheme::Eip191 {
+            return Err(SignatureError::MultisigEvm);
+        }
+
+        if proof.format.message(&self.signer, h160_add)? != proof.message {
+            return Err(SignatureError::MessageMismatch);
+        }
+        let signer = decode_ss58(&self.signer).map_err(SignatureError::InvalidSs58)?;
+        proof.scheme.verify(&signer.account, &proof.message, &proof.signature)
+    }
+}
+
+#[test]
+fn test_multisig_account_id() {
+    use sp_core::{sr25519, Pair};
+
+    let account = |seed: &str| sr25519::Pair::from_string(seed, None).unwrap().public().0;
+    let signatories = [account("//Charlie"), account("//Alice"), account("//Bob")];
+    let multisig = multisig_account_id(&signatories, 2);
+    assert_eq!(
+        crate::encode_ss58(&multisig, 42),
+        "5DjYJStmdZ2rcqXbXGX7TW85JsrW6uG4y9MUcLq2BoPMpRA7"
+    );
+    assert_ne!(multisig_account_id(&signatories, 3), multisig);
+    assert_eq!(compact_len(100), vec![0x91, 0x01]);
+}
+
+#[test]
+fn test_multisig_signature() {
+    use crate::{encode_ss58, verify_proof, MessageFormat};
+    use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+
+    let h160_add = "0xb794f5ea0ba39494ce839613fffba74279579268";
+    let pairs = [7u8, 8, 9].map(|seed| sr25519::Pair::from_seed(&[seed; 32]));
+    let signatories = pairs.iter().map(|pair| pair.public().to_ss58check()).collect::<Vec<_>>();
+    let accounts = pairs.iter().map(|pair| pair.public().0).collect::<Vec<_>>();
+    let ksm = encode_ss58(&multisig_account_id(&accounts, 2), 42);
+    let message = MessageFormat::WrappedBytes.message("", h160_add).unwrap();
+    let signature = hex::encode(pairs[1].sign(&message));
+    let multisig = |signatories: &[String], threshold| Multisig {
+        signatories: signatories.to_vec(),
+        threshold,
+    };
+
+    let proof = check_multisig_signature(&ksm, h160_add, &signature, &multisig(&signatories, 2)).unwrap();
+    let signer = proof.multisig.as_ref().unwrap();
+    assert_eq!((signer.signer.as_str(), signer.threshold), (signatories[1].as_str(), 2));
+    assert_eq!(verify_proof(&ksm, h160_add, &proof), Ok(()));
+
+    let errors = [
+        (multisig(&signatories, 3), SignatureError::MultisigMismatch),
+        (multisig(&signatories, 4), SignatureError::InvalidThreshold(3)),
+        (multisig(&signatories[..1], 1), SignatureError::TooFewSignatories),
+        (
+            multisig(&[signatories[0].clone(), signatories[0].clone()], 2),
+            SignatureError::DuplicateSignatories,
+        ),
+        (
+            multisig(&["5Fake".to_string(), signatories[0].clone()], 2),
+            SignatureError::InvalidSignatory("5Fake".to_string()),
+        ),
+    ];
+    for (multisig, error) in errors {
+        assert_eq!(check_multisig_signature(&ksm, h160_add, &signature, &multisig), Err(error));
+    }
+    assert_eq!(
+        SignatureError::InvalidThreshold(3).to_string(),
+        "The threshold must be between 1 and the number of signatories (3)"
+    );
+
+    let outsider = hex::encode(sr25519::Pair::from_seed(&[1; 32]).sign(&message));
+    assert_eq!(
+        check_multisig_signature(&ksm, h160_add, &outsider, &multisig(&signatories, 2)),
+        Err(SignatureError::NoSignatory)
+    );
+}
+#[test]
+fn test_multisig_rejects_evm_signatures() {
+    use crate::{encode_ss58, personal_message_hash, verify_proof};
+    use sp_core::{crypto::Ss58Codec, hashing::keccak_256, sr25519, Pair};
+
+    let pairs = [7u8, 8].map(|seed| sr25519::Pair::from_seed(&[seed; 32]));
+    let signatories = pairs.iter().map(|pair| pair.public().to_ss58check()).collect::<Vec<_>>();
+    let accounts = pairs.iter().map(|pair| pair.public().0).collect::<Vec<_>>();
+    let ksm = encode_ss58(&multisig_account_id(&accounts, 2), 42);
+    let multisig = Multisig {
+        signatories: signatories.clone(),
+        threshold: 2,
+    };
+
+    // an EVM key signing a signatory's address, tried until the signature also reads as a
+    // `MultiSignature`, which one key in about 128 does
+    let (h160_add, signature) = (1u8..=255)
+        .find_map(|seed| {
+            let key = libsecp256k1::SecretKey::parse(&[seed; 32]).ok()?;
+            let public = libsecp256k1::PublicKey::from_secret_key(&key);
+            let h160_add = format!("0x{}", hex::encode(&keccak_256(&public.serialize()[1..])[12..]));
+            let hash = libsecp256k1::Message::parse(&personal_message_hash(signatories[0].as_bytes()));
+            let (signature, recovery_id) = libsecp256k1::sign(&hash, &key);
+            let mut signature = signature.serialize().to_vec();
+            signature.push(recovery_id.serialize() + 27);
+            (signature[0] <= 1).then(|| (h160_add, hex::encode(signature)))
+        })
+        .unwrap();
+    assert!(multi_signature(&hex::decode(&signature).unwrap()).is_some());
+    assert_eq!(
+        check_multisig_signature(&ksm, &h160_add, &signature, &multisig),
+        Err(SignatureError::MultisigEvm)
+    );
+
+    // nor does a stored proof of that kind verify again
+    let mut proof = check_signature(&signatories[0], &h160_add, &signature).unwrap();
+    proof.multisig = Some(MultisigProof {
+        signer: signatories[0].clone(),
+        signatories,
+        threshold: 2,
+    });
+    assert_eq!(verify_proof(&ksm, &h160_add, &proof), Err(SignatureError::MultisigEvm));
+}
@@ -0,0 +1,572 @@
+use std::str::FromStr;
+
+use blake2_rfc::blake2b::blake2b;
+use ed25519_dalek::{PublicKey as EdPublicKey, Signature as EdSignature, Verifier as _};
+use schnorrkel::{signing_context, PublicKey, Signature};
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::address::{check_h160, decode_ss58};
+use crate::error::SignatureError;
+use crate::multisig::MultisigProof;
+
+const MSG_WRAP_PREFIX: &str = "<Bytes>";
+const MSG_WRAP_POSTFIX: &str = "</Bytes>";
+
+/// Length of an sr25519 or ed25519 signature.
+pub const SIGNATURE_LEN: usize = 64;
+/// Length of a `personal_sign` signature: r, s and the recovery id.
+pub const EVM_SIGNATURE_LEN: usize = 65;
+/// Length of a substrate ECDSA signature, the same layout as `personal_sign`.
+const ECDSA_SIGNATURE_LEN: usize = 65;
+
+/// How a signature proves the two addresses belong together: the substrate key signs the GLMR
+/// address, or for `Eip191` the EVM key signs the SS58 address with `personal_sign`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    Sr25519,
+    Ed25519,
+    /// Substrate ECDSA, only accepted inside a SCALE encoded `MultiSignature`.
+    Ecdsa,
+    Eip191,
+}
+
+impl Scheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scheme::Sr25519 => "sr25519",
+            Scheme::Ed25519 => "ed25519",
+            Scheme::Ecdsa => "ecdsa",
+            Scheme::Eip191 => "eip191",
+        }
+    }
+
+    /// `signer` is the public key of the substrate account, or the H160 address for `Eip191`.
+    pub fn verify(&self, signer: &[u8], message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+        match self {
+            Scheme::Sr25519 => check_ss58_signature(signer, message, signature),
+            Scheme::Ed25519 => check_ed_signature(signer, message, signature),
+            Scheme::Ecdsa => check_ecdsa_signature(signer, message, signature),
+            Scheme::Eip191 => check_evm_signature(signer, message, signature),
+        }
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = SignatureError;
+
+    fn from_str(scheme: &str) -> Result<Self, Self::Err> {
+        match scheme {
+            "sr25519" => Ok(Scheme::Sr25519),
+            "ed25519" => Ok(Scheme::Ed25519),
+            "ecdsa" => Ok(Scheme::Ecdsa),
+            "eip191" => Ok(Scheme::Eip191),
+            other => Err(SignatureError::UnknownScheme(other.to_string())),
+        }
+    }
+}
+
+/// How the GLMR address is encoded into the signed message. polkadot.js wraps the raw bytes in
+/// `<Bytes>` tags, subkey, mobile wallets and Ledger sign the bytes as they are or the address text.
+/// `Ss58Text` is the other direction, the SS58 address text signed by the EVM key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    WrappedBytes,
+    Bytes,
+    WrappedHex,
+    Hex,
+    WrappedPrefixedHex,
+    PrefixedHex,
+    Ss58Text,
+}
+
+/// Tried in this order, the polkadot.js format first as most signatures use it.
+pub const MESSAGE_FORMATS: [MessageFormat; 6] = [
+    MessageFormat::WrappedBytes,
+    MessageFormat::Bytes,
+    MessageFormat::WrappedHex,
+    MessageFormat::Hex,
+    MessageFormat::WrappedPrefixedHex,
+    MessageFormat::PrefixedHex,
+];
+
+impl MessageFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageFormat::WrappedBytes => "wrapped_bytes",
+            MessageFormat::Bytes => "bytes",
+            MessageFormat::WrappedHex => "wrapped_hex",
+            MessageFormat::Hex => "hex",
+            MessageFormat::WrappedPrefixedHex => "wrapped_0x_hex",
+            MessageFormat::PrefixedHex => "0x_hex",
+            MessageFormat::Ss58Text => "ss58_text",
+        }
+    }
+
+    fn wrapped(&self) -> bool {
+        matches!(
+            self,
+            MessageFormat::WrappedBytes | MessageFormat::WrappedHex | MessageFormat::WrappedPrefixedHex
+        )
+    }
+
+    /// The bytes signed for the addresses. Text formats keep the address as it was submitted,
+    /// case included, with or without the `0x` prefix.
+    pub fn message(&self, ss58_add: &str, h160_add: &str) -> Result<Vec<u8>, SignatureError> {
+        let h160_add = h160_add.strip_prefix("0x").unwrap_or(h160_add);
+        let unwrapped_msg: Vec<u8> = match self {
+            MessageFormat::Ss58Text => ss58_add.as_bytes().to_vec(),
+            MessageFormat::WrappedBytes | MessageFormat::Bytes => {
+                check_h160(h160_add).map_err(|_| SignatureError::InvalidH160)?.to_vec()
+            }
+            MessageFormat::WrappedHex | MessageFormat::Hex => h160_add.as_bytes().to_vec(),
+            MessageFormat::WrappedPrefixedHex | MessageFormat::PrefixedHex => {
+                format!("0x{}", h160_add).into_bytes()
+            }
+        };
+
+        if !self.wrapped() {
+            return Ok(unwrapped_msg);
+        }
+        let mut msg = MSG_WRAP_PREFIX.as_bytes().to_vec();
+        msg.extend(unwrapped_msg);
+        msg.extend(MSG_WRAP_POSTFIX.as_bytes());
+        Ok(msg)
+    }
+}
+
+impl FromStr for MessageFormat {
+    type Err = SignatureError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        MESSAGE_FORMATS
+            .iter()
+            .chain(&[MessageFormat::Ss58Text])
+            .find(|known| known.as_str() == format)
+            .copied()
+            .ok_or_else(|| SignatureError::UnknownFormat(format.to_string()))
+    }
+}
+
+/// What a signature was accepted on, enough to check it again with [`verify_proof`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub signature: Vec<u8>,
+    /// The exact bytes signed.
+    pub message: Vec<u8>,
+    pub scheme: Scheme,
+    pub format: MessageFormat,
+    /// Set when a signatory signed for a multisig SS58 address.
+    pub multisig: Option<MultisigProof>,
+}
+
+/// One scheme and message format tried for a signature, with why it did not verify.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attempt {
+    pub scheme: Scheme,
+    pub format: MessageFormat,
+    /// The signature bytes checked, without the type byte of a `MultiSignature`.
+    pub signature: Vec<u8>,
+    /// The bytes the signature was checked against.
+    pub message: Vec<u8>,
+    pub result: Result<(), SignatureError>,
+}
+
+/// Decodes a signature given as hex, with or without `0x`, or as base64, standard or URL safe.
+/// Input made of hex digits only is read as hex.
+pub fn decode_signature(signature: &str) -> Result<Vec<u8>, SignatureError> {
+    let signature = signature.trim();
+    let (digits, prefixed) = match signature.strip_prefix("0x") {
+        Some(digits) => (digits, true),
+        None => (signature, false),
+    };
+    let is_hex = digits.chars().all(|c| c.is_ascii_hexdigit());
+
+    if prefixed || is_hex {
+        if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(SignatureError::Character(c));
+        }
+        if digits.len() % 2 == 1 {
+            return Err(SignatureError::OddHexLength(digits.len()));
+        }
+        return hex::decode(digits).map_err(|_| SignatureError::Encoding);
+    }
+
+    base64::decode_config(digits, base64::STANDARD)
+        .or_else(|_| base64::decode_config(digits, base64::URL_SAFE))
+        .map_err(|_| {
+            let base64_char = |c: char| c.is_ascii_alphanumeric() || "+/-_=".contains(c);
+            match digits.chars().find(|&c| !base64_char(c)) {
+                Some(c) => SignatureError::Character(c),
+                None => SignatureError::Encoding,
+            }
+        })
+}
+
+/// The scheme and signature of a SCALE encoded `MultiSignature`: a type byte, 0 for ed25519,
+/// 1 for sr25519 and 2 for ECDSA, followed by the signature.
+pub fn multi_signature(signature: &[u8]) -> Option<(Scheme, &[u8])> {
+    let (scheme, len) = match signature.first()? {
+        0 => (Scheme::Ed25519, SIGNATURE_LEN),
+        1 => (Scheme::Sr25519, SIGNATURE_LEN),
+        2 => (Scheme::Ecdsa, ECDSA_SIGNATURE_LEN),
+        _ => return None,
+    };
+    if signature.len() != len + 1 {
+        return None;
+    }
+    Some((scheme, &signature[1..]))
+}
+
+/// Tries a signature with every scheme and message format [`check_signature`] accepts, in the
+/// same order, without stopping at the first match. Errors are for inputs nothing can be tried on.
+pub fn diagnose_signature(ss58_add: &str, h160_add: &str, signature: &str) -> Result<Vec<Attempt>, SignatureError> {
+    let h160_add = h160_add.strip_prefix("0x").unwrap_or(h160_add);
+
+    let h160 = check_h160(h160_add).map_err(|_| SignatureError::InvalidH160)?;
+    let sig = decode_signature(signature)?;
+    let acc = decode_ss58(ss58_add).map_err(SignatureError::InvalidSs58)?;
+
+    let mut attempts = Vec::new();
+    let mut attempt = |scheme: Scheme, format: MessageFormat, signer: &[u8], signature: &[u8]| {
+        let message = format.message(ss58_add, h160_add)?;
+        let result = scheme.verify(signer, &message, signature);
+        attempts.push(Attempt {
+            scheme,
+            format,
+            signature: signature.to_vec(),
+            message,
+            result,
+        });
+        Ok::<_, SignatureError>(())
+    };
+
+    // the type byte names the scheme, a 65 byte `personal_sign` signature may start with 0 or 1 too
+    if let Some((scheme, inner)) = multi_signature(&sig) {
+        for format in MESSAGE_FORMATS {
+            attempt(scheme, format, &acc.account, inner)?;
+        }
+    }
+    match sig.len() {
+        EVM_SIGNATURE_LEN => attempt(Scheme::Eip191, MessageFormat::Ss58Text, &h160, &sig)?,
+        SIGNATURE_LEN => {
+            for format in MESSAGE_FORMATS {
+                for scheme in [Scheme::Sr25519, Scheme::Ed25519] {
+                    attempt(scheme, format, &acc.account, &sig)?;
+                }
+            }
+        }
+        _ => (),
+    }
+
+    if attempts.is_empty() {
+        return Err(match sig.len() {
+            len if len == ECDSA_SIGNATURE_LEN + 1 => SignatureError::MultiSignatureType(sig[0]),
+            len => SignatureError::Length(len),
+        });
+    }
+    Ok(attempts)
+}
+
+/// Verifies a signature linking the SS58 and H160 addresses. `personal_sign` signatures are
+/// recognised by their length, a SCALE encoded `MultiSignature` by its type byte, others are
+/// tried in each of the [`MESSAGE_FORMATS`] with sr25519 and ed25519. See [`decode_signature`]
+/// for the encodings accepted.
+pub fn check_signature(ss58_add: &str, h160_add: &str, signature: &str) -> Result<Proof, SignatureError> {
+    let attempts = diagnose_signature(ss58_add, h160_add, signature)?;
+
+    if let Some(attempt) = attempts.iter().find(|attempt| attempt.result.is_ok()) {
+        return Ok(Proof {
+            signature: attempt.signature.clone(),
+            message: attempt.message.clone(),
+            scheme: attempt.scheme,
+            format: attempt.format,
+            multisig: None,
+        });
+    }
+    // the first error is the one of the most common format and scheme
+    match attempts.into_iter().next() {
+        Some(attempt) => Err(attempt.result.unwrap_err()),
+        None => Err(SignatureError::NotVerified),
+    }
+}
+
+/// Checks a proof again: the message must be the one for the addresses in the recorded format
+/// and the signature must verify with the recorded scheme.
+pub fn verify_proof(ss58_add: &str, h160_add: &str, proof: &Proof) -> Result<(), SignatureError> {
+    if let Some(multisig) = &proof.multisig {
+        return multisig.verify(ss58_add, h160_add, proof);
+    }
+    if proof.format.message(ss58_add, h160_add)? != proof.message {
+        return Err(SignatureError::MessageMismatch);
+    }
+
+    let signer = match proof.scheme {
+        Scheme::Eip191 => check_h160(h160_add).map_err(|_| SignatureError::InvalidH160)?.to_vec(),
+        _ => decode_ss58(ss58_add).map_err(SignatureError::InvalidSs58)?.account.to_vec(),
+    };
+    proof.scheme.verify(&signer, &proof.message, &proof.signature)
+}
+
+/// Verifies an sr25519 signature made with the `substrate` signing context.
+pub fn check_ss58_signature(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+    let sig = Signature::from_bytes(signature).map_err(|_| SignatureError::Malformed)?;
+    let pk = PublicKey::from_bytes(pubkey).map_err(|_| SignatureError::InvalidPublicKey)?;
+    let context = signing_context(b"substrate");
+
+    pk.verify(context.bytes(message), &sig)
+        .map_err(|_| SignatureError::NotVerified)
+}
+
+pub fn check_ed_signature(pubkey: &[u8], message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+    let sig = EdSignature::from_bytes(signature).map_err(|_| SignatureError::Malformed)?;
+    let pk = EdPublicKey::from_bytes(pubkey).map_err(|_| SignatureError::InvalidPublicKey)?;
+
+    pk.verify(message, &sig).map_err(|_| SignatureError::NotVerified)
+}
+
+/// Verifies a substrate ECDSA signature: the key recovered from the signature of the blake2 hash
+/// of the message must hash to the account id, as for substrate accounts of ECDSA keys.
+pub fn check_ecdsa_signature(account: &[u8], message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+    if signature.len() != ECDSA_SIGNATURE_LEN {
+        return Err(SignatureError::Malformed);
+    }
+    let sig = libsecp256k1::Signature::parse_standard_slice(&signature[..64])
+        .map_err(|_| SignatureError::Malformed)?;
+    let recovery_id = libsecp256k1::RecoveryId::parse(signature[64])
+        .or_else(|_| libsecp256k1::RecoveryId::parse_rpc(signature[64]))
+        .map_err(|_| SignatureError::Malformed)?;
+
+    let hash = libsecp256k1::Message::parse_slice(blake2b(32, &[], message).as_bytes())
+        .map_err(|_| SignatureError::Malformed)?;
+    let key = libsecp256k1::recover(&hash, &sig, &recovery_id).map_err(|_| SignatureError::NotVerified)?;
+    if blake2b(32, &[], &key.serialize_compressed()).as_bytes() != account {
+        return Err(SignatureError::NotVerified);
+    }
+    Ok(())
+}
+
+pub(crate) fn keccak_256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    let mut hash = [0; 32];
+    keccak.update(data);
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// The hash `personal_sign` signs: the message prefixed as described in EIP-191.
+pub fn personal_message_hash(message: &[u8]) -> [u8; 32] {
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message);
+    keccak_256(&prefixed)
+}
+
+/// Verifies a `personal_sign` signature by recovering the signer and comparing its address to `h160`.
+pub fn check_evm_signature(h160: &[u8], message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+    if signature.len() != EVM_SIGNATURE_LEN {
+        return Err(SignatureError::Malformed);
+    }
+    let sig = libsecp256k1::Signature::parse_standard_slice(&signature[..64])
+        .map_err(|_| SignatureError::Malformed)?;
+    // wallets use 27 and 28 for the recovery id, some libraries 0 and 1
+    let recovery_id = libsecp256k1::RecoveryId::parse_rpc(signature[64])
+        .or_else(|_| libsecp256k1::RecoveryId::parse(signature[64]))
+        .map_err(|_| SignatureError::Malformed)?;
+
+    let hash = libsecp256k1::Message::parse(&personal_message_hash(message));
+    let key = libsecp256k1::recover(&hash, &sig, &recovery_id).map_err(|_| SignatureError::NotVerified)?;
+    // the address is the last 20 bytes of the hash of the uncompressed key, without its tag byte
+    if keccak_256(&key.serialize()[1..])[12..] != *h160 {
+        return Err(SignatureError::WrongSigner);
+    }
+    Ok(())
+}
+#[test]
+fn test_signature_unstripped_hex() {
+    let ss58_address = &"14AkzFjCFtdwzCJnnfPxgwL87W1h7AHFdzjKh9q9YaojWFxx".to_string();
+    let h160_add = &"0xb794f5ea0ba39494ce839613fffba74279579268".to_string();
+    let signature = &"0xc67b20ee54a52ba6636e8f41f7aa984a47916ef17a119d441d29a97ac6ebfa6921f649cd3a02084df393a6614f3ac699aca98bdb5ccf5504dd74fd6e3f6dd48a".to_string();
+    let check = check_signature(ss58_address, h160_add, signature);
+    assert!(check.is_ok(), "err: {}", check.unwrap_err());
+}
+
+#[test]
+fn test_signature_stripped_hex() {
+    let ss58_address = &"14AkzFjCFtdwzCJnnfPxgwL87W1h7AHFdzjKh9q9YaojWFxx".to_string();
+    let h160_add = &"b794f5ea0ba39494ce839613fffba74279579268".to_string();
+    let signature = &"c67b20ee54a52ba6636e8f41f7aa984a47916ef17a119d441d29a97ac6ebfa6921f649cd3a02084df393a6614f3ac699aca98bdb5ccf5504dd74fd6e3f6dd48a".to_string();
+    let check = check_signature(ss58_address, h160_add, signature);
+    assert!(check.is_ok(), "err: {}", check.unwrap_err());
+}
+#[test]
+fn test_signature_wrong_signature() {
+    let ss58_address = &"14AkzFjCFtdwzCJnnfPxgwL87W1h7AHFdzjKh9q9YaojWFxx".to_string();
+    let h160_add = &"b794f5ea0ba39494ce839613fffba74279579268".to_string();
+    let signature = &"367b20ee54a52ba6636e8f41f7aa984a47916ef17a119d441d29a97ac6ebfa6921f649cd3a02084df393a6614f3ac699aca98bdb5ccf5504dd74fd6e3f6dd48a".to_string();
+    let check = check_signature(ss58_address, h160_add, signature);
+    assert!(check.is_err(), "Signature was expected to fail but passed");
+}
+
+#[test]
+fn test_signature_ed25519() {
+    let ss58_address = &"EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu".to_string();
+    let h160_add = &"b794f5ea0ba39494ce839613fffba74279579268".to_string();
+    let signature = &"fb275c30af9eceb9e0370f80896c223fdc728e590bc5deefb776f78ac914c8b3be21800a9f959bbb7e03ce4b745965c82261dfbcc3d7c7906a9bd7a4f855380a".to_string();
+
+    let check = check_signature(ss58_address, h160_add, signature);
+    assert!(check.is_ok(), "err: {}", check.unwrap_err());
+}
+
+#[test]
+fn test_signature_message_formats() {
+    use sp_core::{crypto::Ss58Codec, ed25519, sr25519, Pair};
+
+    let h160_add = "0xB794F5eA0ba39494cE839613fffBA74279579268";
+    let sr = sr25519::Pair::from_seed(&[7; 32]);
+    let ed = ed25519::Pair::from_seed(&[7; 32]);
+
+    for format in MESSAGE_FORMATS {
+        // the GLMR formats do not depend on the SS58 address
+        let message = format.message("", h160_add).unwrap();
+        let signed = [
+            (sr.public().to_ss58check(), hex::encode(sr.sign(&message)), Scheme::Sr25519),
+            (ed.public().to_ss58check(), hex::encode(ed.sign(&message)), Scheme::Ed25519),
+        ];
+        for (ss58_address, signature, scheme) in signed {
+            let proof = check_signature(&ss58_address, h160_add, &signature).unwrap();
+            assert_eq!((proof.format, proof.scheme), (format, scheme));
+            assert_eq!(proof.message, message);
+        }
+    }
+
+    let text = String::from_utf8(MessageFormat::WrappedPrefixedHex.message("", h160_add).unwrap()).unwrap();
+    assert_eq!(text, "<Bytes>0xB794F5eA0ba39494cE839613fffBA74279579268</Bytes>");
+    assert_eq!(MessageFormat::Bytes.message("", h160_add).unwrap().len(), 20);
+    assert_eq!("0x_hex".parse(), Ok(MessageFormat::PrefixedHex));
+
+    // a trailing newline, as `echo` adds, is not one of the formats
+    let signature = hex::encode(sr.sign(b"B794F5eA0ba39494cE839613fffBA74279579268\n"));
+    assert!(check_signature(&sr.public().to_ss58check(), h160_add, &signature).is_err());
+}
+
+#[test]
+fn test_signature_evm_signs_ss58() {
+    let ss58_address = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+    let key = libsecp256k1::SecretKey::parse(&[9; 32]).unwrap();
+    let public = libsecp256k1::PublicKey::from_secret_key(&key);
+    let h160_add = format!("0x{}", hex::encode(&keccak_256(&public.serialize()[1..])[12..]));
+
+    let sign = |message: &[u8]| {
+        let hash = libsecp256k1::Message::parse(&personal_message_hash(message));
+        let (signature, recovery_id) = libsecp256k1::sign(&hash, &key);
+        let mut signature = signature.serialize().to_vec();
+        signature.push(recovery_id.serialize() + 27);
+        hex::encode(signature)
+    };
+
+    let proof = check_signature(ss58_address, &h160_add, &sign(ss58_address.as_bytes())).unwrap();
+    assert_eq!((proof.scheme, proof.format), (Scheme::Eip191, MessageFormat::Ss58Text));
+    assert_eq!(proof.message, ss58_address.as_bytes());
+    assert_eq!(verify_proof(ss58_address, &h160_add, &proof), Ok(()));
+
+    let other = "14AkzFjCFtdwzCJnnfPxgwL87W1h7AHFdzjKh9q9YaojWFxx";
+    assert_eq!(
+        check_signature(other, &h160_add, &sign(ss58_address.as_bytes())),
+        Err(SignatureError::WrongSigner)
+    );
+    let other_key = "0x0000000000000000000000000000000000000001";
+    assert!(check_signature(ss58_address, other_key, &sign(ss58_address.as_bytes())).is_err());
+}
+
+#[test]
+fn test_diagnose_signature() {
+    let ksm = "EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu";
+    let glmr = "0xb794f5ea0ba39494ce839613fffba74279579268";
+    let signature = "fb275c30af9eceb9e0370f80896c223fdc728e590bc5deefb776f78ac914c8b3be21800a9f959bbb7e03ce4b745965c82261dfbcc3d7c7906a9bd7a4f855380a";
+
+    let attempts = diagnose_signature(ksm, glmr, signature).unwrap();
+    assert_eq!(attempts.len(), MESSAGE_FORMATS.len() * 2);
+    let verified: Vec<_> = attempts
+        .iter()
+        .filter(|attempt| attempt.result.is_ok())
+        .map(|attempt| (attempt.scheme, attempt.format))
+        .collect();
+    assert_eq!(verified, vec![(Scheme::Ed25519, MessageFormat::WrappedBytes)]);
+    assert_eq!(attempts[0].result, Err(SignatureError::Malformed));
+    assert_eq!(attempts[3].result, Err(SignatureError::NotVerified));
+
+    let evm = diagnose_signature(ksm, glmr, &"11".repeat(EVM_SIGNATURE_LEN)).unwrap();
+    assert_eq!(evm.len(), 1);
+    assert_eq!((evm[0].scheme, evm[0].format), (Scheme::Eip191, MessageFormat::Ss58Text));
+    assert!(evm[0].result.is_err());
+
+    assert_eq!(diagnose_signature(ksm, glmr, "zz!"), Err(SignatureError::Character('!')));
+}
+
+#[test]
+fn test_signature_encodings() {
+    use sp_core::{crypto::Ss58Codec, ecdsa, ed25519, sr25519, Pair};
+
+    let h160_add = "0xb794f5ea0ba39494ce839613fffba74279579268";
+    let message = MessageFormat::WrappedBytes.message("", h160_add).unwrap();
+    let sr = sr25519::Pair::from_seed(&[7; 32]);
+    let ed = ed25519::Pair::from_seed(&[7; 32]);
+    let sr_address = sr.public().to_ss58check();
+    let sr_signature = sr.sign(&message).0;
+
+    for signature in [
+        hex::encode(sr_signature),
+        base64::encode(sr_signature),
+        base64::encode_config(sr_signature, base64::URL_SAFE_NO_PAD),
+    ] {
+        let proof = check_signature(&sr_address, h160_add, &signature).unwrap();
+        assert_eq!(proof.signature, sr_signature.to_vec());
+    }
+
+    // MultiSignature: the type byte picks the scheme and is not part of the stored signature
+    let mut multi = vec![0];
+    multi.extend(ed.sign(&message).0);
+    let proof = check_signature(&ed.public().to_ss58check(), h160_add, &hex::encode(&multi)).unwrap();
+    assert_eq!((proof.scheme, proof.signature), (Scheme::Ed25519, multi[1..].to_vec()));
+
+    let mut multi = vec![1];
+    multi.extend(sr_signature);
+    let attempts = diagnose_signature(&sr_address, h160_add, &base64::encode(&multi)).unwrap();
+    assert_eq!(attempts.len(), MESSAGE_FORMATS.len() + 1);
+    assert_eq!((attempts[0].scheme, &attempts[0].result), (Scheme::Sr25519, &Ok(())));
+    assert_eq!(attempts.last().unwrap().scheme, Scheme::Eip191);
+
+    let ecdsa = ecdsa::Pair::from_seed(&[7; 32]);
+    let account = sp_core::hashing::blake2_256(&ecdsa.public().0);
+    let ecdsa_address = crate::encode_ss58(&account, 2);
+    let mut multi = vec![2];
+    multi.extend(ecdsa.sign(&message).0);
+    let proof = check_signature(&ecdsa_address, h160_add, &hex::encode(&multi)).unwrap();
+    assert_eq!(proof.scheme, Scheme::Ecdsa);
+    assert_eq!(verify_proof(&ecdsa_address, h160_add, &proof), Ok(()));
+    assert_eq!(
+        check_signature(&sr_address, h160_add, &hex::encode(&multi)),
+        Err(SignatureError::NotVerified)
+    );
+
+    multi[0] = 3;
+    assert_eq!(
+        check_signature(&ecdsa_address, h160_add, &hex::encode(&multi)),
+        Err(SignatureError::MultiSignatureType(3))
+    );
+    assert_eq!(
+        check_signature(&sr_address, h160_add, &hex::encode(&sr_signature[..63])),
+        Err(SignatureError::Length(63))
+    );
+    assert_eq!(
+        check_signature(&sr_address, h160_add, &hex::encode(sr_signature)[1..]),
+        Err(SignatureError::OddHexLength(127))
+    );
+    assert_eq!(
+        check_signature(&sr_address, h160_add, "0xzz"),
+        Err(SignatureError::Character('z'))
+    );
+    assert_eq!(
+        SignatureError::Length(63).to_string(),
+        "The signature is 63 bytes, expected 64, or 65 for MetaMask."
+    );
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+
+use base58::FromBase58;
+
+use crate::address::{decode_ss58, Ss58Address};
+use crate::error::Ss58Error;
+
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+// the checksum constants of bech32 and bech32m
+const BECH32_CONSTANTS: [u32; 2] = [1, 0x2bc8_30a3];
+
+/// What an address given by a user looks like, whatever it was given for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddressKind {
+    Ss58(Ss58Address),
+    /// 32 hex encoded bytes, a substrate public key rather than its address.
+    PublicKey([u8; 32]),
+    H160([u8; 20]),
+    /// An address of a chain using bech32, such as Cosmos or Bitcoin, with its human readable part.
+    Bech32 { hrp: String },
+    Unknown(UnknownAddress),
+}
+
+/// Why an input is not an address of any known format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnknownAddress {
+    Empty,
+    Whitespace,
+    /// The number of hex digits, neither an H160 address nor a public key.
+    HexLength(usize),
+    /// Base58 that does not decode to an SS58 address.
+    Ss58(Ss58Error),
+    /// A character none of the formats use.
+    Character(char),
+}
+
+impl fmt::Display for UnknownAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnknownAddress::Empty => f.write_str("is empty"),
+            UnknownAddress::Whitespace => f.write_str("contains whitespace"),
+            UnknownAddress::HexLength(digits) => write!(
+                f,
+                "has {} hex digits, an H160 address has 40 and a public key 64",
+                digits
+            ),
+            UnknownAddress::Ss58(why) => write!(f, "is not a valid SS58 address, {}", why),
+            UnknownAddress::Character(c) => write!(f, "contains '{}', which no address format uses", c),
+        }
+    }
+}
+
+impl fmt::Display for AddressKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressKind::Ss58(address) => match network_name(address.prefix) {
+                Some(network) => write!(f, "a {} SS58 address", network),
+                None => write!(f, "an SS58 address of network {}", address.prefix),
+            },
+            AddressKind::PublicKey(_) => f.write_str("a 32 byte public key"),
+            AddressKind::H160(_) => f.write_str("an H160 address"),
+            AddressKind::Bech32 { hrp } => write!(f, "a bech32 address for {}", hrp),
+            AddressKind::Unknown(why) => write!(f, "not an address, it {}", why),
+        }
+    }
+}
+
+/// The name of a network from its SS58 prefix, for the common ones.
+pub fn network_name(prefix: u16) -> Option<&'static str> {
+    match prefix {
+        0 => Some("Polkadot"),
+        2 => Some("Kusama"),
+        42 => Some("generic substrate"),
+        1284 => Some("Moonbeam"),
+        1285 => Some("Moonriver"),
+        _ => None,
+    }
+}
+
+/// Classifies an input by its format. Surrounding whitespace is ignored, hex may leave out `0x`.
+pub fn sniff_address(input: &str) -> AddressKind {
+    let input = input.trim();
+    if input.is_empty() {
+        return AddressKind::Unknown(UnknownAddress::Empty);
+    }
+    if input.contains(char::is_whitespace) {
+        return AddressKind::Unknown(UnknownAddress::Whitespace);
+    }
+
+    let prefixed = input.starts_with("0x") || input.starts_with("0X");
+    let digits = if prefixed { &input[2..] } else { input };
+    let is_hex = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex && digits.len() == 40 {
+        let mut h160 = [0; 20];
+        if hex::decode_to_slice(digits, &mut h160).is_ok() {
+            return AddressKind::H160(h160);
+        }
+    }
+    if is_hex && digits.len() == 64 {
+        let mut key = [0; 32];
+        if hex::decode_to_slice(digits, &mut key).is_ok() {
+            return AddressKind::PublicKey(key);
+        }
+    }
+    if prefixed {
+        return match digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            Some(c) => AddressKind::Unknown(UnknownAddress::Character(c)),
+            None => AddressKind::Unknown(UnknownAddress::HexLength(digits.len())),
+        };
+    }
+
+    let ss58_error = match decode_ss58(input) {
+        Ok(address) => return AddressKind::Ss58(address),
+        Err(why) => why,
+    };
+    if let Some(hrp) = bech32_hrp(input) {
+        return AddressKind::Bech32 { hrp };
+    }
+    if is_hex {
+        return AddressKind::Unknown(UnknownAddress::HexLength(digits.len()));
+    }
+    let base58 = |c: char| c.to_string().from_base58().is_ok();
+    match input.chars().find(|&c| !base58(c)) {
+        Some(c) if ss58_error == Ss58Error::NotBase58 => AddressKind::Unknown(UnknownAddress::Character(c)),
+        _ => AddressKind::Unknown(UnknownAddress::Ss58(ss58_error)),
+    }
+}
+
+/// The human readable part of a bech32 or bech32m string with a valid checksum.
+fn bech32_hrp(input: &str) -> Option<String> {
+    // either case is allowed, mixing them is not
+    if input != input.to_ascii_lowercase() && input != input.to_ascii_uppercase() {
+        return None;
+    }
+    let input = input.to_ascii_lowercase();
+    let separator = input.rfind('1')?;
+    let (hrp, data) = (&input[..separator], &input[separator + 1..]);
+    if hrp.is_empty() || data.len() < 6 || !hrp.bytes().all(|b| (33..=126).contains(&b)) {
+        return None;
+    }
+
+    let mut values: Vec<u8> = hrp.bytes().map(|b| b >> 5).collect();
+    values.push(0);
+    values.extend(hrp.bytes().map(|b| b & 31));
+    for c in data.chars() {
+        values.push(BECH32_CHARSET.find(c)? as u8);
+    }
+    if BECH32_CONSTANTS.contains(&bech32_polymod(&values)) {
+        Some(hrp.to_string())
+    } else {
+        None
+    }
+}
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    let mut checksum = 1u32;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ff_ffff) << 5) ^ u32::from(*value);
+        for (i, generator) in BECH32_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+    checksum
+}
+
+#[test]
+fn test_sniff_address() {
+    let kusama = sniff_address(" EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyu\n");
+    assert_eq!(kusama.to_string(), "a Kusama SS58 address");
+    let account = match kusama {
+        AddressKind::Ss58(address) => address.account,
+        other => panic!("not SS58: {:?}", other),
+    };
+
+    let polkadot = crate::encode_ss58(&account, 0);
+    assert_eq!(sniff_address(&polkadot).to_string(), "a Polkadot SS58 address");
+    assert_eq!(sniff_address(&hex::encode(account)), AddressKind::PublicKey(account));
+    assert_eq!(
+        sniff_address(&format!("0x{}", hex::encode(account))),
+        AddressKind::PublicKey(account)
+    );
+
+    for h160 in [
+        "0xb794f5ea0ba39494ce839613fffba74279579268",
+        "b794f5ea0ba39494ce839613fffba74279579268",
+        "0xB794F5eA0ba39494cE839613fffBA74279579268",
+    ] {
+        assert!(matches!(sniff_address(h160), AddressKind::H160(_)), "{}", h160);
+    }
+
+    assert_eq!(
+        sniff_address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+        AddressKind::Bech32 { hrp: "bc".to_string() }
+    );
+    assert_eq!(
+        sniff_address("A12UEL5L"),
+        AddressKind::Bech32 { hrp: "a".to_string() }
+    );
+    // a broken checksum is not bech32
+    assert!(matches!(
+        sniff_address("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5"),
+        AddressKind::Unknown(_)
+    ));
+
+    let unknown = |input: &str| match sniff_address(input) {
+        AddressKind::Unknown(why) => why,
+        other => panic!("{} is {:?}", input, other),
+    };
+    assert_eq!(unknown("  "), UnknownAddress::Empty);
+    assert_eq!(unknown("EYudu chUna"), UnknownAddress::Whitespace);
+    assert_eq!(unknown("0xb794f5ea0ba39494ce839613fffba742795792"), UnknownAddress::HexLength(38));
+    assert_eq!(unknown("0xb794f5ea0ba39494ce839613fffba74279579g"), UnknownAddress::Character('g'));
+    assert_eq!(
+        unknown("EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiyv"),
+        UnknownAddress::Ss58(Ss58Error::Checksum)
+    );
+    assert_eq!(unknown("EYuduchUnaQwZpQeLSHfbizV7myJ5XAx3Fyo1RZPamiBiOu"), UnknownAddress::Character('O'));
+    assert_eq!(
+        sniff_address("0xb794f5ea").to_string(),
+        "not an address, it has 8 hex digits, an H160 address has 40 and a public key 64"
+    );
+}